@@ -0,0 +1,36 @@
+use bevy::prelude::*;
+
+/// Something the player asked the game to do. Mouse, touch, keyboard and gamepad input are all
+/// turned into these, so the game logic reads one stream and never looks at raw input.
+/// Positions are in world coordinates.
+#[derive(Message, Clone, Copy, PartialEq, Debug)]
+pub enum GameAction {
+    // Pointer (mouse or finger)
+    Press(Vec2),
+    Drag(Vec2), // Sent every frame once the pointer has been held or slid far enough to drag
+    Drop(Vec2),
+    QuickMove(Vec2), // Double-click or double-tap: send the card to a foundation, or failing that a tableau column
+    // Pile cursor (keyboard or gamepad)
+    Left,
+    Right,
+    Up,
+    Down,
+    Select, // Pick up the card under the cursor, or drop the held one
+    Cancel, // Put the held card down again
+    ToFoundation,
+    NextTarget, // Jump to the next place the held card can go
+    // Any input
+    Draw,
+    Undo,
+    Redo,
+}
+
+impl GameAction {
+    /// Where the pointer went down, if this is a press
+    pub fn press_position(&self) -> Option<Vec2> {
+        match self {
+            GameAction::Press(position) => Some(*position),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::layout::Layout;
+use crate::setup::setup_foundations;
+use crate::utils::{shuffle_deck, standard_deck};
+use crate::theme::CardImages;
+use tracing::debug;
+
+// Canfield board: 4 columns under the foundations, the reserve under the waste
+const CANFIELD_COLUMNS: usize = 4;
+const CANFIELD_RESERVE: usize = 13;
+
+/// Spawns the Canfield board: a 13-card reserve with its top card face-up, one card on the first
+/// foundation (its rank is the base for every foundation this deal), one card on each of the
+/// 4 columns, and the remaining 34 cards in the stock, dealt three at a time
+pub fn setup_canfield_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    let mut deck = standard_deck(0);
+    shuffle_deck(&mut deck, deal_number as u64);
+    let mut cards = deck.into_iter();
+
+    setup_foundations(commands);
+
+    // Reserve: a squared-up pile under the waste, only the top card face-up
+    let reserve_position = Layout::tableau_position(5, Layout::KLONDIKE_COLUMNS, 0);
+    for i in 0..CANFIELD_RESERVE {
+        let Some(card) = cards.next() else { break };
+        let position = Vec3::new(reserve_position.x, reserve_position.y, (i + 1) as f32);
+
+        if i == CANFIELD_RESERVE - 1 {
+            create_card_entity(commands, card_images, position, card, true, (ReservePile, Draggable, CardFront, OriginalPosition(position)));
+        } else {
+            create_card_entity(commands, card_images, position, card, false, (ReservePile, CardBack, OriginalPosition(position)));
+        }
+    }
+    commands.insert_resource(ReservePosition(Some(reserve_position)));
+
+    // The next card starts the first foundation and sets the base rank for the others
+    if let Some(card) = cards.next() {
+        let foundation_position = Layout::top_row_position(0, Layout::KLONDIKE_COLUMNS) + Vec3::Z;
+        create_card_entity(commands, card_images, foundation_position, card, true, (FoundationPile, CardFront, OriginalPosition(foundation_position)));
+
+        let mut foundation_piles = vec![Vec::new(); 4];
+        foundation_piles[0].push(card);
+        commands.insert_resource(FoundationPiles(foundation_piles));
+        commands.insert_resource(RankRules { foundation_base: card.value, wrap: true });
+        debug!("Canfield deal {}: foundations start at {}", deal_number, card.value);
+    }
+
+    // One card on each column, using the same columns as the first 4 Klondike stacks
+    let mut tableau_positions = Vec::new();
+    for column in 0..CANFIELD_COLUMNS {
+        let position = Layout::tableau_position(column, Layout::KLONDIKE_COLUMNS, 0);
+        tableau_positions.push(position);
+
+        let Some(card) = cards.next() else { break };
+        create_card_entity(
+            commands,
+            card_images,
+            position,
+            card,
+            true,
+            (
+                Draggable,
+                TableauPile,
+                CardFront,
+                OriginalPosition(position),
+            ),
+        );
+    }
+    commands.insert_resource(TableauPositions(tableau_positions));
+
+    // Waste pile placeholder and the stock, dealt by the Klondike stock click
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.3, 0.3, 0.3),
+            custom_size: Some(CARD_SIZE),
+            ..default()
+        },
+        Transform::from_translation(WastePosition::default().0),
+        WastePile,
+    ));
+    create_card_entity(
+        commands,
+        card_images,
+        StockPosition::default().0,
+        CardId::new(0, CardSuit::Spades, 1), // Dummy card - not important for stock pile
+        false,
+        (
+            StockPile,
+            CardBack,
+        ),
+    );
+    commands.insert_resource(StockCards(cards.collect()));
+}
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use bevy::asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crate::components::*;
+use tracing::{debug, warn};
+
+/// Atlas slots: the 52 faces in CardSuit order, Ace to King, then the back
+pub const ATLAS_CARDS: usize = 53;
+pub const BACK_INDEX: usize = 52;
+
+// Every card image is shrunk to this many pixels per board unit. Enough to stay sharp when the
+// board is zoomed in on a large window, while the whole deck still fits in a 2048 pixel texture.
+const TILE_SCALE: f32 = 2.5;
+const ATLAS_COLUMNS: u32 = 10;
+const TILE_PADDING: u32 = 2; // Keeps neighbouring cards from bleeding into each other's edges
+const SAMPLES: u32 = 3; // Per side, for each atlas pixel: a small box filter
+const PLAIN_BACK_COLOR: [u8; 4] = [28, 53, 87, 255]; // Navy, like the Classic deck's back
+const PLAIN_BACK_MARGIN: u32 = 10;
+
+/// The atlas slot of a card's face
+pub fn face_index(suit: CardSuit, value: u8) -> usize {
+    suit as usize * 13 + (value as usize).clamp(1, 13) - 1
+}
+
+/// The whole deck packed into one texture. Cards draw their slot of it, so turning a card over is
+/// just a change of index, and switching deck theme or card back replaces the one image every card
+/// shares. Slots whose image was missing are left plain: a white face, which the fallback face system
+/// draws on, or a plain navy back.
+#[derive(Resource)]
+pub struct CardAtlas {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    missing: [bool; ATLAS_CARDS],
+}
+
+impl CardAtlas {
+    /// Whether the slot's image was missing (or the theme draws its faces), so it holds a plain card
+    pub fn is_missing(&self, index: usize) -> bool {
+        self.missing.get(index).copied().unwrap_or(true)
+    }
+
+    /// An atlas with nothing packed into it, for tests that spawn cards
+    #[cfg(test)]
+    pub fn empty() -> Self {
+        CardAtlas { image: Handle::default(), layout: Handle::default(), missing: [true; ATLAS_CARDS] }
+    }
+}
+
+/// Size of one atlas slot in pixels
+fn tile_size() -> UVec2 {
+    (CARD_SIZE * TILE_SCALE).round().as_uvec2()
+}
+
+fn atlas_layout() -> TextureAtlasLayout {
+    let tile = tile_size();
+    let rows = (ATLAS_CARDS as u32).div_ceil(ATLAS_COLUMNS);
+    let cell = tile + UVec2::splat(TILE_PADDING);
+    let mut layout = TextureAtlasLayout::new_empty(UVec2::new(ATLAS_COLUMNS, rows) * cell);
+    for index in 0..ATLAS_CARDS as u32 {
+        let corner = UVec2::new(index % ATLAS_COLUMNS, index / ATLAS_COLUMNS) * cell;
+        layout.add_texture(URect::from_corners(corner, corner + tile));
+    }
+    layout
+}
+
+/// A card image shrunk to the size of one atlas slot: RGBA pixels, row by row
+pub type CardTile = Vec<u8>;
+
+/// Shrinks a loaded card image to the size of one atlas slot. Run on the async compute pool as each
+/// image comes in, so the full-size image can be dropped straight away. None if its pixel format can't
+/// be read; the slot is then left plain.
+pub fn shrink_card_image(image: Image, index: usize) -> Option<CardTile> {
+    let converted;
+    let image = if image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb {
+        &image
+    } else {
+        converted = image.convert(TextureFormat::Rgba8UnormSrgb); // Such as a greyscale or 16 bit PNG
+        let Some(converted) = converted.as_ref() else {
+            warn!("Card image for atlas slot {} is in a format that can't be packed", index);
+            return None;
+        };
+        converted
+    };
+    let source_data = image.data.as_ref()?;
+
+    let tile = tile_size();
+    let mut pixels = vec![0u8; (tile.x * tile.y * 4) as usize];
+    shrink_into(&mut pixels, tile.x, URect::from_corners(UVec2::ZERO, tile), source_data, image.size());
+    Some(pixels)
+}
+
+/// Packs the shrunk card images, one per slot (None for a missing one), into a new atlas image
+pub fn pack_card_atlas(tiles: &[Option<CardTile>]) -> (Image, [bool; ATLAS_CARDS]) {
+    let layout = atlas_layout();
+    let size = layout.size;
+    let mut data = vec![0u8; (size.x * size.y * 4) as usize];
+    let mut missing = [true; ATLAS_CARDS];
+
+    for (index, rect) in layout.textures.iter().enumerate() {
+        match tiles.get(index).and_then(Option::as_ref) {
+            Some(tile) => {
+                missing[index] = false;
+                fill_tile(&mut data, size.x, *rect, |x, y| {
+                    let offset = ((y * rect.width() + x) * 4) as usize;
+                    tile[offset..offset + 4].try_into().unwrap()
+                });
+            }
+            None if index == BACK_INDEX => fill_tile(&mut data, size.x, *rect, |x, y| {
+                let inside = x >= PLAIN_BACK_MARGIN && y >= PLAIN_BACK_MARGIN && x + PLAIN_BACK_MARGIN < rect.width() && y + PLAIN_BACK_MARGIN < rect.height();
+                if inside { PLAIN_BACK_COLOR } else { [255; 4] }
+            }),
+            None => fill_tile(&mut data, size.x, *rect, |_x, _y| [255; 4]),
+        }
+    }
+
+    debug!("Packed the deck into a {}x{} atlas", size.x, size.y);
+    let image = Image::new(
+        Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD, // Nothing reads it back on the CPU
+    );
+    (image, missing)
+}
+
+/// Puts a freshly packed atlas in place: the first one becomes the CardAtlas, and later ones (after a
+/// deck theme or card back switch) replace its image, which every card picks up at once
+pub fn install_card_atlas(
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    layouts: &mut Assets<TextureAtlasLayout>,
+    card_atlas: Option<&mut CardAtlas>,
+    packed: (Image, [bool; ATLAS_CARDS]),
+) {
+    let (image, missing) = packed;
+    match card_atlas {
+        Some(card_atlas) => {
+            if let Err(error) = images.insert(&card_atlas.image, image) {
+                warn!("Couldn't replace the card atlas: {}", error);
+                return;
+            }
+            card_atlas.missing = missing;
+        }
+        None => commands.insert_resource(CardAtlas {
+            image: images.add(image),
+            layout: layouts.add(atlas_layout()),
+            missing,
+        }),
+    }
+}
+
+/// Copies a whole source image into `rect` of the atlas, averaging a few source pixels for each atlas pixel
+fn shrink_into(atlas: &mut [u8], atlas_width: u32, rect: URect, source: &[u8], source_size: UVec2) {
+    let scale = source_size.as_vec2() / rect.size().as_vec2();
+    fill_tile(atlas, atlas_width, rect, |x, y| {
+        let mut sum = [0u32; 4];
+        for sample_y in 0..SAMPLES {
+            for sample_x in 0..SAMPLES {
+                let source_x = ((x as f32 + (sample_x as f32 + 0.5) / SAMPLES as f32) * scale.x) as u32;
+                let source_y = ((y as f32 + (sample_y as f32 + 0.5) / SAMPLES as f32) * scale.y) as u32;
+                let offset = ((source_y.min(source_size.y - 1) * source_size.x + source_x.min(source_size.x - 1)) * 4) as usize;
+                let alpha = source[offset + 3] as u32;
+                // Weighted by alpha, so transparent corners don't darken the edge
+                for channel in 0..3 {
+                    sum[channel] += source[offset + channel] as u32 * alpha;
+                }
+                sum[3] += alpha;
+            }
+        }
+        let count = SAMPLES * SAMPLES;
+        if sum[3] == 0 {
+            return [0; 4];
+        }
+        [(sum[0] / sum[3]) as u8, (sum[1] / sum[3]) as u8, (sum[2] / sum[3]) as u8, (sum[3] / count) as u8]
+    });
+}
+
+/// Sets every pixel of `rect` in the atlas to `pixel(x, y)`, where x and y count from the rect's corner
+fn fill_tile(atlas: &mut [u8], atlas_width: u32, rect: URect, pixel: impl Fn(u32, u32) -> [u8; 4]) {
+    for y in 0..rect.height() {
+        for x in 0..rect.width() {
+            let offset = (((rect.min.y + y) * atlas_width + rect.min.x + x) * 4) as usize;
+            atlas[offset..offset + 4].copy_from_slice(&pixel(x, y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_image(size: UVec2, pixel: [u8; 4]) -> Image {
+        Image::new_fill(
+            Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            &pixel,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::MAIN_WORLD,
+        )
+    }
+
+    #[test]
+    fn a_card_image_shrinks_to_one_slot_of_the_same_colour() {
+        let red = [200, 30, 40, 255];
+        let tile = shrink_card_image(solid_image(tile_size() * 4, red), 0).unwrap();
+        assert_eq!(tile.len() as u32, tile_size().x * tile_size().y * 4);
+        assert!(tile.chunks(4).all(|pixel| pixel == red));
+    }
+
+    #[test]
+    fn slots_without_a_tile_are_packed_plain_and_marked_missing() {
+        let red = [200, 30, 40, 255];
+        let mut tiles = vec![None; ATLAS_CARDS];
+        tiles[face_index(CardSuit::Spades, 1)] = shrink_card_image(solid_image(tile_size(), red), 0);
+        let (image, missing) = pack_card_atlas(&tiles);
+
+        assert!(!missing[face_index(CardSuit::Spades, 1)]);
+        assert!(missing[face_index(CardSuit::Hearts, 1)]);
+        assert!(missing[BACK_INDEX]);
+
+        let layout = atlas_layout();
+        let pixel_at = |index: usize| {
+            let corner = layout.textures[index].min;
+            let offset = ((corner.y * layout.size.x + corner.x) * 4) as usize;
+            image.data.as_ref().unwrap()[offset..offset + 4].to_vec()
+        };
+        assert_eq!(pixel_at(face_index(CardSuit::Spades, 1)), red);
+        assert_eq!(pixel_at(face_index(CardSuit::Hearts, 1)), [255; 4]); // A white face to draw on
+    }
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use crate::components::*;
+use crate::feedback::spawn_toast;
+use crate::settings::{save_setting, setting};
+use crate::tween::Animating;
+use tracing::debug;
+
+const BACKS_FOLDER: &str = "sprites/cards/backs"; // Every PNG in here is offered as a card back
+const DECK_BACK_NAME: &str = "Deck"; // The back the deck theme comes with
+
+/// One card back design, named after its image file
+pub struct CardBackDesign {
+    pub name: String,
+    image: Option<String>, // None for the deck theme's own back
+}
+
+/// The card back designs to choose from and the one in use. --back picks it by name and B switches to the next.
+#[derive(Resource)]
+pub struct CardBacks {
+    pub designs: Vec<CardBackDesign>,
+    pub current: usize,
+}
+
+impl CardBacks {
+    /// The deck theme's own back followed by every image in the backs folder, with `chosen` in use if it names one
+    pub fn load(chosen: Option<&str>) -> Self {
+        let mut images: Vec<String> = std::fs::read_dir(FileAssetReader::get_base_path().join("assets").join(BACKS_FOLDER))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|filename| filename.ends_with(".png"))
+            .collect();
+        images.sort();
+
+        let mut designs = vec![CardBackDesign { name: DECK_BACK_NAME.to_string(), image: None }];
+        designs.extend(images.into_iter().map(|filename| CardBackDesign {
+            name: display_name(filename.trim_end_matches(".png")),
+            image: Some(format!("{}/{}", BACKS_FOLDER, filename)),
+        }));
+        debug!("{} card backs to choose from", designs.len());
+
+        let current = chosen
+            .and_then(|chosen| designs.iter().position(|design| design.name.replace(' ', "").eq_ignore_ascii_case(&chosen.replace(' ', ""))))
+            .unwrap_or(0);
+        CardBacks { designs, current }
+    }
+
+    pub fn current(&self) -> &CardBackDesign {
+        &self.designs[self.current]
+    }
+
+    /// Asset path of the chosen back, or None to use the deck theme's
+    pub fn current_image(&self) -> Option<&str> {
+        self.current().image.as_deref()
+    }
+}
+
+/// "EvilFerris" becomes "Evil Ferris"
+fn display_name(stem: &str) -> String {
+    let mut name = String::new();
+    for (i, letter) in stem.chars().enumerate() {
+        if i > 0 && letter.is_uppercase() {
+            name.push(' ');
+        }
+        name.push(letter);
+    }
+    name
+}
+
+/// Finds the card back designs at startup, before the first deal, and picks the one named by --back
+/// or the one the player chose last time
+pub fn load_card_backs(mut commands: Commands) {
+    let chosen = setting("--back", "back");
+    commands.insert_resource(CardBacks::load(chosen.as_deref()));
+}
+
+/// B switches to the next card back, once no card is moving or turning over, and remembers it for next time.
+/// It is loaded into the card atlas, which redraws the stock and every face-down card with it at once.
+pub fn card_back_switch_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut card_backs: ResMut<CardBacks>,
+    animating_query: Query<(), (With<Card>, Animating)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+    if !animating_query.is_empty() {
+        debug!("Not switching card back while cards are moving");
+        return;
+    }
+
+    card_backs.current = (card_backs.current + 1) % card_backs.designs.len();
+    let design = card_backs.current();
+    debug!("Switching to card back {:?}", design.name);
+    save_setting("back", &design.name);
+    spawn_toast(&mut commands, format!("Card back: {}", design.name));
+    next_state.set(GameState::Loading);
+}
@@ -1,72 +1,124 @@
 use bevy::prelude::*;
+use crate::actions::GameAction;
 use crate::components::*;
+use crate::card_placement::release_free_cell;
+use crate::card_validation::{collect_cards_above, find_card_under_cursor, CardPlay};
+use crate::ferris::award_stack_points;
+use crate::game_mode::BoardState;
+use crate::hit_test::board_piles;
+use crate::sound::{PlaySound, SoundEffect};
 use crate::utils::{can_place_on_foundation, find_best_tableau_target};
 use tracing::debug;
 
+/// Double-click (or double-tap) quick moves for every pile: the top card goes to a foundation if it can,
+/// otherwise onto the best tableau column
+pub fn quick_move_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut sounds: MessageWriter<PlaySound>,
+    selected_card: Res<SelectedCard>,
+    play: CardPlay,
+) {
+    let CardPlay { mut board, pile_positions, mut transform_query, card_data_query, entity_query, animating_query, tableau_cards_query } = play;
+    let game_mode = *board.rules.mode;
+    let piles = board_piles(game_mode, &pile_positions);
+
+    for action in game_actions.read() {
+        let GameAction::QuickMove(cursor_pos) = *action else { continue };
+        if selected_card.0.is_some() {
+            continue;
+        }
+
+        // Only the top card of a pile can be sent anywhere on its own
+        let Some(hit) = find_card_under_cursor(cursor_pos, &piles, &entity_query, &transform_query, &card_data_query) else { continue };
+        let entity = hit.entity;
+        if animating_query.contains(entity) {
+            continue; // Still moving or turning over
+        }
+        if !collect_cards_above(entity, &entity_query, &transform_query).is_empty() {
+            continue;
+        }
+        let Ok(card_data) = card_data_query.get(entity) else { continue };
+        debug!("QUICK MOVE on entity {:?} in {:?} at {}: card {:?} (value: {})", entity, hit.pile, hit.index, card_data.suit, card_data.value);
+
+        // Try foundation move first (Spider and Ferris foundations only take complete stacks)
+        if game_mode.foundation_takes_single_cards() && try_foundation_move_simple(entity, &mut transform_query, card_data, &mut board, &pile_positions.foundations, &mut commands) {
+            debug!("QUICK MOVE: Successfully moved card to foundation");
+            sounds.write(PlaySound(SoundEffect::Foundation));
+            continue;
+        }
+
+        let tableau_cards: Vec<(Entity, Vec3, CardData)> = tableau_cards_query
+            .iter()
+            .filter_map(|(entity, card_data)| {
+                let transform = transform_query.get(entity).ok()?;
+                Some((entity, transform.translation, card_data.clone()))
+            })
+            .collect();
+        if try_tableau_move_simple(entity, &mut transform_query, card_data, &tableau_cards, &pile_positions.tableau.0, &mut board, &mut commands) {
+            debug!("QUICK MOVE: Successfully moved card to tableau");
+            sounds.write(PlaySound(SoundEffect::Drop));
+            award_stack_points(game_mode, &mut board.game_score, 1);
+        } else {
+            debug!("QUICK MOVE: Both foundation and tableau moves failed");
+        }
+    }
+}
+
 /// Simple foundation move function that reuses existing validation logic
 pub fn try_foundation_move_simple(
     entity: Entity,
     transform_query: &mut Query<&mut Transform, (With<Card>, With<Draggable>)>,
     card_data: &CardData,
-    foundation_piles: &mut ResMut<FoundationPiles>,
+    board: &mut BoardState,
+    foundation_positions: &FoundationPositions,
     commands: &mut Commands,
 ) -> bool {
-    // Find the appropriate foundation pile for this card
-    let foundation_index = match card_data.suit {
-        CardSuit::Hearts => 0,
-        CardSuit::Diamonds => 1,
-        CardSuit::Clubs => 2,
-        CardSuit::Spades => 3,
+    // Find the first foundation pile this card can go on (its suit's pile, or an empty one for an Ace)
+    let Some(foundation_index) = board.foundation_piles.0.iter().position(|pile| can_place_on_foundation(card_data, pile, &board.rules.ranks)) else {
+        debug!("FOUNDATION REJECTED: Card {:?} (value: {}, suit: {:?}) cannot be placed on any foundation pile",
+               card_data.suit, card_data.value, card_data.suit);
+        return false;
     };
-    
-    let foundation_pile = &foundation_piles.0[foundation_index];
-    
-    // Use existing validation logic from utils.rs
-    if can_place_on_foundation(card_data, foundation_pile) {
-        debug!("FOUNDATION PLACEMENT: Card {:?} (value: {}, suit: {:?}) can be placed on foundation pile {}", 
-               card_data.suit, card_data.value, card_data.suit, foundation_index);
-        
-        // Store original position before moving
-        let original_position = if let Ok(transform) = transform_query.get(entity) {
-            transform.translation
-        } else {
-            return false;
-        };
-        
-        // Calculate foundation pile position
-        let foundation_start_x = -(6.0 * 100.0) / 2.0;
-        let foundation_x = foundation_start_x + (foundation_index as f32 * 100.0);
-        let foundation_y = WINDOW_HEIGHT / 2.0 - 100.0;
-        
-        // Move the card to the foundation pile
-        let foundation_pos = Vec3::new(foundation_x, foundation_y, foundation_pile.len() as f32 + 1.0);
-        if let Ok(mut transform) = transform_query.get_mut(entity) {
-            transform.translation = foundation_pos;
-        }
-        
-        // Update the FoundationPiles resource
-        foundation_piles.0[foundation_index].push((card_data.suit, card_data.value));
-        
-        // Remove tableau/waste components and add foundation component
-        commands.entity(entity)
-            .remove::<TableauPile>()
-            .remove::<WastePile>()
-            .remove::<SkippedWasteCard>()
-            .remove::<StockPile>()
-            .remove::<Draggable>() // Foundation cards cannot be moved
-            .insert(FoundationPile)
-            .insert(OriginalPosition(foundation_pos));
-        
-        // Trigger card flipping for face-down cards underneath (use original position)
-        commands.spawn(NeedsFlipUnderneath(original_position));
-        
-        true
+    let Some(foundation_position) = foundation_positions.0.get(foundation_index) else { return false; };
+
+    debug!("FOUNDATION PLACEMENT: Card {:?} (value: {}, suit: {:?}) can be placed on foundation pile {}",
+           card_data.suit, card_data.value, card_data.suit, foundation_index);
+
+    // Store original position before moving
+    let original_position = if let Ok(transform) = transform_query.get(entity) {
+        transform.translation
     } else {
-        debug!("FOUNDATION REJECTED: Card {:?} (value: {}, suit: {:?}) cannot be placed on foundation pile {} (empty: {}, top: {:?})", 
-               card_data.suit, card_data.value, card_data.suit, foundation_index, foundation_pile.is_empty(), 
-               foundation_pile.last());
-        false
+        return false;
+    };
+
+    // Move the card to the foundation pile
+    let foundation_pos = Vec3::new(foundation_position.x, foundation_position.y, board.foundation_piles.0[foundation_index].len() as f32 + 1.0);
+    if let Ok(mut transform) = transform_query.get_mut(entity) {
+        transform.translation = foundation_pos;
     }
+
+    // Update the FoundationPiles resource
+    board.foundation_piles.0[foundation_index].push(card_data.id());
+    release_free_cell(&mut board.free_cells, entity);
+
+    // Remove tableau/waste components and add foundation component
+    commands.entity(entity)
+        .remove::<TableauPile>()
+        .remove::<WastePile>()
+        .remove::<SkippedWasteCard>()
+        .remove::<StockPile>()
+        .remove::<FreeCellPile>()
+        .remove::<ReservePile>()
+        .remove::<Draggable>() // Foundation cards cannot be moved
+        .insert(FoundationPile)
+        .insert(OriginalPosition(foundation_pos));
+
+    // Trigger card flipping for face-down cards underneath (use original position)
+    commands.spawn(NeedsFlipUnderneath(original_position));
+    board.moves.write(MoveMade);
+
+    true
 }
 
 /// Simple tableau move function that reuses existing validation logic
@@ -76,6 +128,7 @@ pub fn try_tableau_move_simple(
     card_data: &CardData,
     tableau_cards: &[(Entity, Vec3, CardData)],
     tableau_positions: &[Vec3],
+    board: &mut BoardState,
     commands: &mut Commands,
 ) -> bool {
     // Store original position before moving
@@ -86,7 +139,7 @@ pub fn try_tableau_move_simple(
     };
     
     // Use existing validation logic from utils.rs
-    if let Some(target_pos) = find_best_tableau_target(card_data, original_position, tableau_cards, tableau_positions, Some(entity)) {
+    if let Some(target_pos) = find_best_tableau_target(card_data, original_position, tableau_cards, tableau_positions, Some(entity), *board.rules.mode, &board.rules.ranks) {
         debug!("TABLEAU PLACEMENT: Card {:?} (value: {}, suit: {:?}) can be placed on tableau at {:?}", 
                card_data.suit, card_data.value, card_data.suit, target_pos);
         
@@ -117,9 +170,13 @@ pub fn try_tableau_move_simple(
             transform.translation = new_position;
         }
         
+        release_free_cell(&mut board.free_cells, entity);
+
         // Update components
         commands.entity(entity)
             .remove::<WastePile>()
+            .remove::<FreeCellPile>()
+            .remove::<ReservePile>()
             .remove::<SkippedWasteCard>()
             .remove::<StockPile>()
             .insert(TableauPile)
@@ -128,6 +185,7 @@ pub fn try_tableau_move_simple(
         
         // Trigger card flipping for face-down cards underneath (use original position)
         commands.spawn(NeedsFlipUnderneath(original_position));
+        board.moves.write(MoveMade);
         
         true
     } else {
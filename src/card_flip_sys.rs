@@ -1,18 +1,25 @@
 use bevy::prelude::*;
 use crate::components::*;
-use crate::utils::{get_card_back_image, get_card_front_image};
+use crate::game_mode::GameMode;
+use crate::hit_test::{board_piles, pile_of};
+use crate::tween::FlipTween;
+use crate::theme::CardImages;
 use tracing::debug;
 
+/// The cards of the columns and of the Canfield reserve, the piles that can have face-down cards to turn over
+type FlippablePileCards<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static CardData), (With<Card>, Or<(With<TableauPile>, With<ReservePile>)>)>;
 
-
+/// Turns over the card left on top of the pile a move took cards from, if it is face-down
 pub fn flip_cards_system(
     mut commands: Commands,
     needs_flip_query: Query<(Entity, &NeedsFlipUnderneath)>,
-    all_transform_query: Query<&Transform, With<Card>>,
-    all_card_data_query: Query<&CardData, With<Card>>,
-    all_entity_query: Query<Entity, With<Card>>,
-    asset_server: Res<AssetServer>,
+    pile_cards_query: FlippablePileCards,
+    game_mode: Res<GameMode>,
+    pile_positions: PilePositions,
+    card_images: CardImages,
 ) {
+    let piles = board_piles(*game_mode, &pile_positions);
+
     for (entity, needs_flip) in needs_flip_query.iter() {
         let target_position = needs_flip.0;
         debug!("Processing flip trigger at position: {:?}", target_position);
@@ -20,80 +27,107 @@ pub fn flip_cards_system(
         // Remove the entity immediately to prevent duplicate processing
         commands.entity(entity).despawn();
         
-        // Find face-down cards at the target position that need to be flipped
-        let mut cards_at_position = Vec::new();
-        
-        // Collect all cards at the target X,Y position with precise detection
-        debug!("Looking for face-down cards at position: {:?}", target_position);
-        for card_entity in all_entity_query.iter() { 
-            if let Ok(transform) = all_transform_query.get(card_entity) {
-                if let Ok(card_data) = all_card_data_query.get(card_entity) {
-
-                     // Skip cards that have already been flipped
-                     if card_data.is_face_up {
-                        debug!("Skipping face-up card");
-                        continue;
-                    }
-                    
-                    let x_distance = (transform.translation.x - target_position.x).abs();
-                    let y_distance = (transform.translation.y - target_position.y).abs();
-                    
-                    debug!("Checking card at {:?}, face_up: {}, x_dist: {:.2}, y_dist: {:.2}, target: {:?}", 
-                           transform.translation, card_data.is_face_up, x_distance, y_distance, target_position);
-                    
-                   
-                    
-                    // Use precise position matching - cards should be at the exact same X,Y position
-                    // Only allow small tolerance for floating point precision and slight stacking offsets
-                    if x_distance < 25.0 && y_distance < 80.0 {
-                        debug!("Found face-down card at position: {:?}", transform.translation);
-                        cards_at_position.push((card_entity, transform.translation.z, card_data));
-                    } else {
-                        debug!("Card too far from target position");
-                    }
-                }
+        // The column (or reserve) the moved cards came from
+        let Some(pile) = pile_of(target_position, &piles).filter(|pile| matches!(pile, PileId::Tableau(_) | PileId::Reserve)) else {
+            debug!("Flip trigger is not on a column, nothing to flip");
+            continue;
+        };
+
+        // Only the pile's top card is turned over. A face-down card further down, however close to
+        // where the moved cards were, still has cards lying on it.
+        let top_card = pile_cards_query
+            .iter()
+            .filter(|(_entity, transform, _card_data)| pile_of(transform.translation, &piles) == Some(pile))
+            .max_by(|a, b| a.1.translation.z.partial_cmp(&b.1.translation.z).unwrap());
+
+        match top_card {
+            Some((card_entity, _transform, card_data)) if !card_data.is_face_up => {
+                debug!("Flipping card entity: {:?}, suit: {:?}, value: {}", 
+                       card_entity, card_data.suit, card_data.value);
+                turn_face_up(&mut commands, &card_images, card_entity, card_data);
+
+                // Add the Draggable component so it can be moved
+                commands.entity(card_entity).insert(Draggable);
+                debug!("Card flip completed successfully");
             }
-        }
-        
-        // Sort by Z position to find the card that was directly underneath
-        cards_at_position.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        
-        debug!("Found {} face-down cards at position", cards_at_position.len());
-        
-        // Find and flip ONLY the topmost face-down card (highest Z position = closest to camera)
-        // Only flip ONE card per movement to prevent multiple flips
-        if let Some((card_entity, _z_pos, card_data)) = cards_at_position.iter().max_by(|a, b| a.1.partial_cmp(&b.1).unwrap()) {
-            debug!("Flipping card entity: {:?}, suit: {:?}, value: {}", 
-                   card_entity, card_data.suit, card_data.value);
-            
-            // Update the card to be face-up
-            commands.entity(*card_entity).insert(CardData {
-                suit: card_data.suit,
-                value: card_data.value,
-                is_face_up: true,
-            });
-            
-            // Add the Draggable component so it can be moved
-            commands.entity(*card_entity).insert(Draggable);
-            
-            // Change the sprite from CardBack to CardFront
-            let front_image_path = get_card_front_image(card_data.suit, card_data.value);
-            debug!("Loading front image: {}", front_image_path);
-           
-            // Remove the old sprite and add the new one
-            commands.entity(*card_entity).remove::<Sprite>();
-            commands.entity(*card_entity).insert(Sprite {
-                image: asset_server.load(front_image_path),
-                custom_size: Some(Vec2::new(80.0, 120.0)),
-                ..default()
-            });
-        
-            // Remove the CardBack component and add CardFront
-            commands.entity(*card_entity).insert(CardFront);
-            debug!("Card flip completed successfully");
-        } else {
-            debug!("No face-down cards found to flip");
+            Some(_) => debug!("Top card of {:?} is already face-up", pile),
+            None => debug!("{:?} is empty, nothing to flip", pile),
         }
     }
 }
 
+/// Turns a face-down card over: the sprite switches from the back to the face halfway through the
+/// flip, which plays the flip sound. Every card turned over in play goes through here.
+pub fn turn_face_up(commands: &mut Commands, card_images: &CardImages, entity: Entity, card_data: &CardData) {
+    commands.entity(entity)
+        .insert(CardData { is_face_up: true, ..card_data.clone() })
+        .insert(FlipTween::to(card_images.front(card_data.suit, card_data.value)))
+        .remove::<CardBack>()
+        .insert(CardFront);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::card_atlas::CardAtlas;
+    use crate::layout::Layout;
+
+    const COLUMN: Vec3 = Vec3::new(-300.0, 0.0, 0.0);
+
+    fn flip_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(GameMode::Klondike);
+        world.insert_resource(CardAtlas::empty());
+        world.insert_resource(TableauPositions(vec![COLUMN, COLUMN + Vec3::X * 100.0]));
+        world.insert_resource(FoundationPositions(Vec::new()));
+        world.insert_resource(FreeCellPositions(Vec::new()));
+        world.insert_resource(StockPosition::default());
+        world.insert_resource(WastePosition::default());
+        world.insert_resource(ReservePosition(None));
+        world
+    }
+
+    // A column fanned out from the bottom card, face-down cards closer together than face-up ones
+    fn spawn_column(world: &mut World, face_down: &[bool]) -> Vec<Entity> {
+        let ys = Layout::default().column_ys(COLUMN.y, face_down);
+        face_down
+            .iter()
+            .zip(ys)
+            .enumerate()
+            .map(|(i, (face_down, y))| {
+                let card_data = CardData { suit: CardSuit::Spades, value: i as u8 + 1, deck: 0, is_face_up: !face_down };
+                world.spawn((Card, card_data, Transform::from_xyz(COLUMN.x, y, i as f32 + 1.0), TableauPile)).id()
+            })
+            .collect()
+    }
+
+    fn is_face_up(world: &World, entity: Entity) -> bool {
+        world.entity(entity).get::<CardData>().unwrap().is_face_up
+    }
+
+    #[test]
+    fn the_face_down_card_left_on_top_is_turned_over() {
+        let mut world = flip_world();
+        let column = spawn_column(&mut world, &[true, true]);
+        let moved_from = world.entity(column[1]).get::<Transform>().unwrap().translation - Vec3::Y * Layout::FACE_DOWN_FAN_OFFSET;
+        world.spawn(NeedsFlipUnderneath(moved_from));
+        world.run_system_once(flip_cards_system).unwrap();
+
+        assert!(is_face_up(&world, column[1]));
+        assert!(world.entity(column[1]).contains::<Draggable>());
+        assert!(!is_face_up(&world, column[0]));
+    }
+
+    #[test]
+    fn face_down_cards_under_face_up_ones_stay_face_down() {
+        let mut world = flip_world();
+        let column = spawn_column(&mut world, &[true, true, false]);
+        let moved_from = world.entity(column[2]).get::<Transform>().unwrap().translation - Vec3::Y * Layout::FAN_OFFSET;
+        world.spawn(NeedsFlipUnderneath(moved_from));
+        world.run_system_once(flip_cards_system).unwrap();
+
+        assert!(!is_face_up(&world, column[0]));
+        assert!(!is_face_up(&world, column[1]));
+    }
+}
@@ -1,249 +1,346 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use crate::components::*;
-use crate::utils::{can_place_on_foundation, can_place_on_tableau_card, find_best_tableau_target};
+use crate::freecell::{count_empty_columns, find_free_cell_target, supermove_limit};
+use crate::game_mode::{BoardState, GameMode};
+use crate::hit_test::{card_under, CardHit};
+use crate::layout::Layout;
+use crate::tween::Animating;
+use crate::utils::{check_foundation, is_valid_run};
 use tracing::debug;
 
-/// Finds a valid drop target for the card with proper solitaire rules
+/// Where a dragged card (and any cards carried on top of it) can be dropped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DropTarget {
+    Foundation(usize, Vec3),
+    Tableau(Vec3),
+    FreeCell(usize, Vec3),
+}
+
+/// Why cards couldn't be dropped where the player tried, in words the player can act on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    WrongColour, // Red must go on black and black on red
+    WrongSuit,
+    WrongRank, // The tableau builds down one rank at a time
+    FoundationOutOfOrder, // Foundations build up one rank at a time
+    FoundationNeedsBase(u8), // An empty foundation only takes this rank (an Ace, unless Canfield dealt another)
+    KingsOnly, // Only Kings fill an empty column
+    FaceDown,
+    TooManyCards(usize), // FreeCell: only this many cards can be moved with the free cells and columns left
+    NoTarget, // Not dropped on any pile
+}
+
+impl MoveError {
+    /// The reason shown to the player
+    pub fn reason(&self) -> String {
+        match self {
+            MoveError::WrongColour => "Red cards go on black cards, black on red".to_string(),
+            MoveError::WrongSuit => "The suits have to match".to_string(),
+            MoveError::WrongRank => "Cards go on a card one rank higher".to_string(),
+            MoveError::FoundationOutOfOrder => "Foundations build up one rank at a time".to_string(),
+            MoveError::FoundationNeedsBase(value) => format!("A foundation has to start with {}", rank_name(*value)),
+            MoveError::KingsOnly => "Only a King can go in an empty column".to_string(),
+            MoveError::FaceDown => "That card is face down".to_string(),
+            MoveError::TooManyCards(limit) => format!("Only {} cards can be moved at once right now", limit),
+            MoveError::NoTarget => "That card can't go there".to_string(),
+        }
+    }
+}
+
+/// A rank as it is read out, with its article: "an Ace", "a 7", "a King"
+fn rank_name(value: u8) -> String {
+    match value {
+        1 => "an Ace".to_string(),
+        8 => "an 8".to_string(),
+        11 => "a Jack".to_string(),
+        12 => "a Queen".to_string(),
+        13 => "a King".to_string(),
+        value => format!("a {}", value),
+    }
+}
+
+/// Finds a valid drop target for the card with proper solitaire rules, or the reason the pile
+/// under the cursor turned it down
+/// `moving_cards` is the picked up card followed by any carried on top of it.
 pub fn find_valid_drop_target(
     cursor_pos: Vec2,
-    selected_entity: Entity,
-    foundation_piles: &FoundationPiles,
-    entity_query: &Query<Entity, (With<Card>, With<Draggable>)>,
-    transform_query: &Query<&mut Transform, (With<Card>, With<Draggable>)>,
+    moving_cards: &[Entity],
+    board: &BoardState,
+    pile_positions: &PilePositions,
+    tableau_cards: &[(Entity, Vec3, CardData)],
     card_data_query: &Query<&CardData>,
-) -> Option<Vec3> {
-    let Ok(selected_card_data) = card_data_query.get(selected_entity) else { return None; };
-    
-    // Check foundation piles first
-    if let Some(target_pos) = find_foundation_target(cursor_pos, foundation_piles, selected_card_data) {
-        return Some(target_pos);
+) -> Result<DropTarget, MoveError> {
+    let Some(selected_card_data) = moving_cards.first().and_then(|entity| card_data_query.get(*entity).ok()) else { return Err(MoveError::NoTarget); };
+    let game_mode = *board.rules.mode;
+    let rank_rules = &*board.rules.ranks;
+    let (foundation_piles, free_cells) = (&*board.foundation_piles, &*board.free_cells);
+
+    // Why the last pile near the cursor said no, if nothing says yes
+    let mut rejection = MoveError::NoTarget;
+
+    // Foundations and free cells only ever take a single card
+    if moving_cards.len() == 1 {
+        if game_mode.foundation_takes_single_cards() {
+            match find_foundation_target(cursor_pos, foundation_piles, &pile_positions.foundations, selected_card_data, rank_rules) {
+                Ok(target) => return Ok(target),
+                Err(MoveError::NoTarget) => {}
+                Err(error) => rejection = error,
+            }
+        }
+
+        if let Some((index, cell_pos)) = find_free_cell_target(cursor_pos, free_cells, &pile_positions.free_cells) {
+            return Ok(DropTarget::FreeCell(index, cell_pos));
+        }
     }
-    
+
     // Check tableau targets (only for tableau cards, not waste pile cards)
-    if let Some(target_pos) = find_tableau_target(cursor_pos, selected_card_data, selected_entity, entity_query, transform_query, card_data_query) {
-        return Some(target_pos);
-    }
-    
-    // Check empty tableau positions (only for Kings)
-    if selected_card_data.value == 13 { // King
-        if let Some(target_pos) = find_empty_tableau_target(cursor_pos) {
-            return Some(target_pos);
+    match find_tableau_target(cursor_pos, selected_card_data, game_mode, rank_rules, moving_cards, tableau_cards) {
+        Ok(target_pos) => {
+            fits_supermove(game_mode, moving_cards, free_cells, &pile_positions.tableau.0, tableau_cards, false)?;
+            return Ok(DropTarget::Tableau(target_pos));
         }
+        Err(MoveError::NoTarget) => {}
+        Err(error) => rejection = error,
     }
-    
-    None
+
+    // Check empty tableau positions (only Kings in Klondike, anything in FreeCell and Spider)
+    if let Some(target_pos) = find_empty_tableau_target(cursor_pos, &pile_positions.tableau.0, tableau_cards, moving_cards) {
+        game_mode.check_fill_empty_column(selected_card_data)?;
+        fits_supermove(game_mode, moving_cards, free_cells, &pile_positions.tableau.0, tableau_cards, true)?;
+        return Ok(DropTarget::Tableau(target_pos));
+    }
+
+    Err(rejection)
 }
 
-/// Finds foundation pile targets with proper validation
-pub fn find_foundation_target(cursor_pos: Vec2, foundation_piles: &FoundationPiles, card_data: &CardData) -> Option<Vec3> {
-    let foundation_y = WINDOW_HEIGHT / 2.0 - 100.0; // This is 260
-    
-    // Foundation piles are at x positions: -150, -50, 50, 150
-    // Waste pile is at x=0, y=260
-    // Only consider foundation area if NOT in waste pile area
-    let waste_pile_x_range = cursor_pos.x >= -50.0 && cursor_pos.x <= 50.0;
-    let foundation_y_range = cursor_pos.y >= foundation_y - 50.0 && cursor_pos.y <= foundation_y + 50.0;
-    
-    // Exclude waste pile area from foundation detection
-    if waste_pile_x_range && foundation_y_range {
-        return None;
+/// FreeCell only moves one card at a time; longer runs need enough empty free cells and columns
+fn fits_supermove(
+    game_mode: GameMode,
+    moving_cards: &[Entity],
+    free_cells: &FreeCells,
+    tableau_positions: &[Vec3],
+    tableau_cards: &[(Entity, Vec3, CardData)],
+    onto_empty_column: bool,
+) -> Result<(), MoveError> {
+    if game_mode != GameMode::FreeCell || moving_cards.len() <= 1 {
+        return Ok(());
     }
-    
-    // Only check foundation if cursor is in foundation area (not tableau area)
-    if !foundation_y_range {
-        return None;
+
+    let empty_free_cells = free_cells.0.iter().filter(|slot| slot.is_none()).count();
+    let mut empty_columns = count_empty_columns(tableau_positions, tableau_cards, moving_cards);
+    if onto_empty_column {
+        // The destination column can't be used as temporary space
+        empty_columns = empty_columns.saturating_sub(1);
+    }
+
+    let limit = supermove_limit(empty_free_cells, empty_columns);
+    if moving_cards.len() > limit {
+        debug!("SUPERMOVE REJECTED: moving {} cards but only {} allowed ({} free cells, {} empty columns)",
+               moving_cards.len(), limit, empty_free_cells, empty_columns);
+        return Err(MoveError::TooManyCards(limit));
     }
 
-    for i in 0..4 {
-        let foundation_x = -150.0 + (i as f32 * 100.0);
-        let foundation_distance = (cursor_pos - Vec2::new(foundation_x, foundation_y)).length();
-        
+    Ok(())
+}
+
+/// Finds foundation pile targets with proper validation
+pub fn find_foundation_target(
+    cursor_pos: Vec2,
+    foundation_piles: &FoundationPiles,
+    foundation_positions: &FoundationPositions,
+    card_data: &CardData,
+    rank_rules: &RankRules,
+) -> Result<DropTarget, MoveError> {
+    let mut rejection = MoveError::NoTarget;
+    for (i, foundation_pos) in foundation_positions.0.iter().enumerate() {
+        let foundation_distance = (cursor_pos - foundation_pos.truncate()).length();
+
         if foundation_distance < 80.0 {
             // Check if this card can be placed on this foundation pile
-            if can_place_on_foundation(card_data, &foundation_piles.0[i]) {
-                let target_pos = Vec3::new(foundation_x, foundation_y, 1.0);
-                return Some(target_pos);
+            match check_foundation(card_data, &foundation_piles.0[i], rank_rules) {
+                Ok(()) => return Ok(DropTarget::Foundation(i, *foundation_pos)),
+                Err(error) => rejection = error,
             }
         }
     }
 
-    None
+    Err(rejection)
 }
 
-/// Finds tableau card targets with proper validation
+/// Finds tableau card targets with proper validation, or why the nearest top card turned the card down
 pub fn find_tableau_target(
     cursor_pos: Vec2,
     selected_card_data: &CardData,
-    selected_entity: Entity,
-    entity_query: &Query<Entity, (With<Card>, With<Draggable>)>,
-    transform_query: &Query<&mut Transform, (With<Card>, With<Draggable>)>,
-    card_data_query: &Query<&CardData>,
-) -> Option<Vec3> {
+    game_mode: GameMode,
+    rank_rules: &RankRules,
+    moving_cards: &[Entity],
+    tableau_cards: &[(Entity, Vec3, CardData)],
+) -> Result<Vec3, MoveError> {
     let mut best_target = None;
     let mut best_distance = f32::INFINITY;
+    let mut rejection = None; // (distance, reason) for the nearest top card that said no
 
     debug!("Looking for tableau target at cursor: {:?}", cursor_pos);
 
-    for entity in entity_query.iter() {
-        // Skip the currently selected card
-        if entity == selected_entity {
+    for (entity, target_pos, target_card_data) in tableau_cards.iter() {
+        // Skip the cards being moved
+        if moving_cards.contains(entity) {
+            continue;
+        }
+
+        let distance = (cursor_pos - target_pos.truncate()).length();
+        if distance >= 80.0 || distance >= best_distance {
+            continue;
+        }
+
+        // Only the top card of a column can be built on
+        let is_top_card = !tableau_cards.iter().any(|(other_entity, other_pos, _other_card_data)| {
+            !moving_cards.contains(other_entity)
+                && other_entity != entity
+                && (other_pos.x - target_pos.x).abs() < 15.0
+                && other_pos.z > target_pos.z
+        });
+        if !is_top_card {
             continue;
         }
-        
-        if let Ok(transform) = transform_query.get(entity) {
-            let target_pos = transform.translation;
-            let distance = (cursor_pos - target_pos.truncate()).length();
-            
-            debug!("Checking entity at {:?}, distance: {:.2}, y: {:.2}", target_pos, distance, target_pos.y);
-            
-            // Exclude waste pile area from tableau detection
-            // Waste pile is at x=0, y=260, foundation piles are at x=-150, -50, 50, 150, y=260
-            let waste_pile_x_range = target_pos.x >= -50.0 && target_pos.x <= 50.0;
-            let foundation_y = WINDOW_HEIGHT / 2.0 - 100.0; // This is 260
-            let foundation_y_range = target_pos.y >= foundation_y - 50.0 && target_pos.y <= foundation_y + 50.0;
-            
-            // Skip waste pile cards (they should only be targets for foundation moves, not tableau moves)
-            if waste_pile_x_range && foundation_y_range {
-                debug!("Skipping waste pile card at {:?}", target_pos);
-                continue;
+
+        match game_mode.check_build_on_tableau(selected_card_data, target_card_data, rank_rules) {
+            Ok(()) => {
+                debug!("DRAG VALID: Card {:?} (value: {}) can be placed on {:?} (value: {})",
+                       selected_card_data.suit, selected_card_data.value,
+                       target_card_data.suit, target_card_data.value);
+                best_target = Some(*target_pos);
+                best_distance = distance;
             }
-            
-            // Consider all other cards for tableau moves
-            if distance < 80.0 && distance < best_distance {
-                // Check if this card can be placed on the target card
-                if let Ok(target_card_data) = card_data_query.get(entity) {
-                    debug!("Target card: suit={:?}, value={}, face_up: {}", target_card_data.suit, target_card_data.value, target_card_data.is_face_up);
-                    if can_place_on_tableau_card(selected_card_data, target_card_data) {
-                        debug!("DRAG VALID: Card {:?} (value: {}, suit: {:?}) can be placed on {:?} (value: {}, suit: {:?})", 
-                               selected_card_data.suit, selected_card_data.value, selected_card_data.suit,
-                               target_card_data.suit, target_card_data.value, target_card_data.suit);
-                        best_target = Some(target_pos);
-                        best_distance = distance;
-                    } else {
-                        debug!("DRAG INVALID: Card {:?} (value: {}, suit: {:?}) cannot be placed on {:?} (value: {}, suit: {:?})", 
-                               selected_card_data.suit, selected_card_data.value, selected_card_data.suit,
-                               target_card_data.suit, target_card_data.value, target_card_data.suit);
-                    }
+            Err(error) => {
+                if rejection.is_none_or(|(rejected_distance, _error)| distance < rejected_distance) {
+                    rejection = Some((distance, error));
                 }
             }
         }
     }
 
-    best_target
+    best_target.ok_or(rejection.map_or(MoveError::NoTarget, |(_distance, error)| error))
 }
 
-/// Finds empty tableau targets (only for Kings)
-pub fn find_empty_tableau_target(cursor_pos: Vec2) -> Option<Vec3> {
-    // Only allow empty tableau placement in the tableau area (more lenient)
-    if cursor_pos.y > 250.0 {
-        return None;
-    }
-    
-    for i in 0..7 {
-        let tableau_x = -300.0 + (i as f32 * 100.0);
-        let tableau_y = 110.0;
-        let tableau_pos = Vec3::new(tableau_x, tableau_y, 0.0);
+/// Finds an empty tableau column near the cursor
+pub fn find_empty_tableau_target(
+    cursor_pos: Vec2,
+    tableau_positions: &[Vec3],
+    tableau_cards: &[(Entity, Vec3, CardData)],
+    moving_cards: &[Entity],
+) -> Option<Vec3> {
+    for tableau_pos in tableau_positions {
         let distance = (cursor_pos - tableau_pos.truncate()).length();
-        
-        if distance < 80.0 {
-            return Some(tableau_pos);
+        if distance >= 80.0 {
+            continue;
+        }
+
+        let is_empty = !tableau_cards.iter().any(|(entity, position, _card_data)| {
+            !moving_cards.contains(entity) && (position.x - tableau_pos.x).abs() < 5.0
+        });
+
+        if is_empty {
+            return Some(*tableau_pos);
         }
     }
 
     None
 }
 
-/// Checks if a card can be dragged (is top card and can lead stack)
+/// Whether two cards sit in the same pile: same column, and both in the top row or both in the tableau
+pub fn is_same_pile(a: Vec3, b: Vec3) -> bool {
+    (a.x - b.x).abs() < 15.0 && (a.y > Layout::TOP_ROW_MIN_Y) == (b.y > Layout::TOP_ROW_MIN_Y)
+}
+
+/// Tableau cards with their data, to be paired up with their positions from CardPlay's transform query
+pub type TableauCardData<'w, 's> = Query<'w, 's, (Entity, &'static CardData), (With<TableauPile>, Without<WastePile>)>;
+
+/// What the drag, quick move and cursor systems need to move cards between piles: the board, where its
+/// piles are, and the cards the player can pick up
+#[derive(SystemParam)]
+pub struct CardPlay<'w, 's> {
+    pub board: BoardState<'w>,
+    pub pile_positions: PilePositions<'w>,
+    pub transform_query: Query<'w, 's, &'static mut Transform, (With<Card>, With<Draggable>)>,
+    pub card_data_query: Query<'w, 's, &'static CardData>,
+    pub entity_query: Query<'w, 's, Entity, (With<Card>, With<Draggable>)>,
+    pub animating_query: Query<'w, 's, (), (With<Card>, Animating)>,
+    pub tableau_cards_query: TableauCardData<'w, 's>,
+}
+
+/// Collects the cards lying on top of this one in its pile, from lowest to highest
+pub fn collect_cards_above(
+    entity: Entity,
+    entity_query: &Query<Entity, (With<Card>, With<Draggable>)>,
+    transform_query: &Query<&mut Transform, (With<Card>, With<Draggable>)>,
+) -> Vec<Entity> {
+    let Ok(transform) = transform_query.get(entity) else { return Vec::new(); };
+    let current_pos = transform.translation;
+
+    let mut cards_above: Vec<(Entity, f32)> = entity_query
+        .iter()
+        .filter(|other_entity| *other_entity != entity)
+        .filter_map(|other_entity| {
+            let other_pos = transform_query.get(other_entity).ok()?.translation;
+            (is_same_pile(current_pos, other_pos) && other_pos.z > current_pos.z + 0.5)
+                .then_some((other_entity, other_pos.z))
+        })
+        .collect();
+
+    cards_above.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    cards_above.into_iter().map(|(other_entity, _z)| other_entity).collect()
+}
+
+/// Checks if a card can be dragged (is top card or leads a valid run for the game mode)
 pub fn can_drag_card(
     entity: Entity,
+    game_mode: GameMode,
+    rank_rules: &RankRules,
     entity_query: &Query<Entity, (With<Card>, With<Draggable>)>,
     transform_query: &Query<&mut Transform, (With<Card>, With<Draggable>)>,
     card_data_query: &Query<&CardData>,
 ) -> bool {
-    let Ok(transform) = transform_query.get(entity) else { return false; };
-    let current_pos = transform.translation;
-    
-    // Check if this card is the top card of its stack
-    for other_entity in entity_query.iter() {
-        if other_entity != entity {
-            if let Ok(other_transform) = transform_query.get(other_entity) {
-                let x_same = (other_transform.translation.x - current_pos.x).abs() < 15.0;
-                let z_higher = other_transform.translation.z > current_pos.z + 0.5;
-                
-                if x_same && z_higher {
-                    return false; // Not the top card
-                }
-            }
-        }
+    let Ok(card_data) = card_data_query.get(entity) else { return false; };
+    if !card_data.is_face_up {
+        return false;
     }
-    
-    // Check if this card can lead a stack
-    if let Ok(card_data) = card_data_query.get(entity) {
-        // Collect all cards above this one
-        let mut cards_above = Vec::new();
-        
-        for other_entity in entity_query.iter() {
-            if other_entity != entity {
-                if let Ok(other_transform) = transform_query.get(other_entity) {
-                    let x_same = (other_transform.translation.x - current_pos.x).abs() < 15.0;
-                    let z_higher = other_transform.translation.z > current_pos.z + 0.5;
-                    
-                    if x_same && z_higher {
-                        if let Ok(other_card_data) = card_data_query.get(other_entity) {
-                            cards_above.push((other_card_data.suit, other_card_data.value));
-                        }
-                    }
-                }
-            }
-        }
-        
-        // If no cards above, this card can lead
-        if cards_above.is_empty() {
-            return true;
+
+    let cards_above = collect_cards_above(entity, entity_query, transform_query);
+
+    // If no cards above, this card can lead
+    if cards_above.is_empty() {
+        return true;
+    }
+
+    // Otherwise everything above must form a valid run with this card
+    let mut all_cards = vec![card_data.id()];
+    for other_entity in cards_above {
+        let Ok(other_card_data) = card_data_query.get(other_entity) else { return false; };
+        if !other_card_data.is_face_up {
+            return false;
         }
-        
-        // Check if this forms a valid descending sequence
-        let mut all_cards = vec![(card_data.suit, card_data.value)];
-        all_cards.extend(cards_above);
-        all_cards.sort_by(|a, b| b.1.cmp(&a.1));
-        
-        return crate::utils::is_valid_stack_sequence(&all_cards);
+        all_cards.push(other_card_data.id());
     }
-    
-    false
+
+    is_valid_run(&all_cards, game_mode.run_rule(), rank_rules)
 }
 
-/// Finds the card under the cursor
+/// Finds the face-up card under the cursor, with the pile it is in and its place in that pile
 pub fn find_card_under_cursor(
     cursor_pos: Vec2,
+    piles: &[(PileId, Vec3)],
     entity_query: &Query<Entity, (With<Card>, With<Draggable>)>,
     transform_query: &Query<&mut Transform, (With<Card>, With<Draggable>)>,
     card_data_query: &Query<&CardData>,
-) -> Option<Entity> {
-    let mut best_entity = None;
-    let mut best_distance = f32::INFINITY;
-
-    for entity in entity_query.iter() {
-        if let Ok(card_data) = card_data_query.get(entity) {
-            // Only allow face-up cards
-            if !card_data.is_face_up {
-                continue;
-            }
-            
-            if let Ok(transform) = transform_query.get(entity) {
-                let card_pos = transform.translation.truncate();
-                let card_bounds = Vec2::new(40.0, 60.0);
-                
-                if (cursor_pos - card_pos).abs().cmplt(card_bounds).all() {
-                    let distance = (cursor_pos - card_pos).length();
-                    if distance < best_distance {
-                        best_entity = Some(entity);
-                        best_distance = distance;
-                    }
-                }
-            }
-        }
-    }
+) -> Option<CardHit> {
+    // Only allow face-up cards
+    let cards: Vec<(Entity, Vec3)> = entity_query
+        .iter()
+        .filter(|entity| card_data_query.get(*entity).is_ok_and(|card_data| card_data.is_face_up))
+        .filter_map(|entity| Some((entity, transform_query.get(entity).ok()?.translation)))
+        .collect();
 
-    best_entity
+    card_under(cursor_pos, piles, &cards)
 }
@@ -1,11 +1,15 @@
 use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use crate::layout::Layout;
 
 pub const WINDOW_WIDTH: f32 = 1280.0;
 pub const WINDOW_HEIGHT: f32 = 720.0;
+pub const CARD_SIZE: Vec2 = Vec2::new(80.0, 120.0);
 
 #[derive(States, Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub enum GameState {
     #[default]
+    Loading, // The deck's images are loading; the board is dealt once they have
     Playing,
 }
 
@@ -15,6 +19,12 @@ pub struct Score;
 #[derive(Resource)]
 pub struct GameScore(pub u32);
 
+#[derive(Component)]
+pub struct ModeLabel; // Shows the current game mode and deal number
+
+#[derive(Component)]
+pub struct WinMessage;
+
 #[derive(Component)]
 pub struct Card;
 
@@ -39,6 +49,12 @@ pub struct StockPile;
 #[derive(Component)]
 pub struct WastePile;
 
+#[derive(Component)]
+pub struct FreeCellPile; // FreeCell: marks the free cell slots and the cards parked in them
+
+#[derive(Component)]
+pub struct ReservePile; // Canfield: the 13-card reserve, only its top card is in play
+
 #[derive(Component)]
 pub struct SkippedWasteCard; // Marks waste cards that have been skipped and are not clickable
 
@@ -52,10 +68,31 @@ pub struct CurrentlyDragging; // Temporary component added during drag, removed
 pub struct CardData {
     pub suit: CardSuit,
     pub value: u8, // 1-13 (Ace=1, Jack=11, Queen=12, King=13)
+    pub deck: u8, // Which deck the card came from, for two-deck games
     pub is_face_up: bool,
 }
 
-#[derive(Component, Clone, Copy, PartialEq, Debug)]
+impl CardData {
+    pub fn id(&self) -> CardId {
+        CardId { deck: self.deck, suit: self.suit, value: self.value }
+    }
+}
+
+/// Identifies one physical card, so the two Ace of Spades in a two-deck game stay distinct
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CardId {
+    pub deck: u8,
+    pub suit: CardSuit,
+    pub value: u8,
+}
+
+impl CardId {
+    pub fn new(deck: u8, suit: CardSuit, value: u8) -> Self {
+        Self { deck, suit, value }
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum CardSuit {
     Hearts,
     Diamonds,
@@ -67,51 +104,133 @@ pub enum CardSuit {
 pub struct SelectedCard(pub Option<Entity>);
 
 #[derive(Resource)]
-pub struct StockCards(pub Vec<(CardSuit, u8)>);
-
+pub struct StockCards(pub Vec<CardId>);
 
 
-#[derive(Component)]
-pub struct CardNumber;
 
 #[derive(Component)]
 pub struct OriginalPosition(pub Vec3);
 
-#[derive(Component)]
-pub struct CoveredCard(pub Option<Entity>); // Points to the card that is covering this one
-
 #[derive(Component)]
 pub struct NeedsFlipUnderneath(pub Vec3); // Marks that a card underneath needs to be flipped
 
-#[derive(Component)]
-pub struct AlreadyFlipped; // Marks that a card has already been flipped in this movement
 
+#[derive(Resource)]
+pub struct TableauPositions(pub Vec<Vec3>);
 
+#[derive(Resource)]
+pub struct FoundationPiles(pub Vec<Vec<CardId>>); // Tracks the entire stack of each foundation pile
 
 #[derive(Resource)]
-pub struct TableauPositions(pub Vec<Vec3>);
+pub struct FoundationPositions(pub Vec<Vec3>); // Where each foundation pile sits for the current game mode
 
 #[derive(Resource)]
-pub struct FoundationPiles(pub Vec<Vec<(CardSuit, u8)>>); // Tracks the entire stack of each foundation pile
+pub struct StockPosition(pub Vec3); // Where the stock sits, so the stock click knows where to look
 
 #[derive(Resource)]
-pub struct ClickedEntity(pub Option<Entity>); // Tracks the last clicked entity for double-click detection 
+pub struct WastePosition(pub Vec3); // Where cards dealt from the stock land
+
+impl Default for StockPosition {
+    fn default() -> Self {
+        StockPosition(Layout::top_row_position(6, Layout::KLONDIKE_COLUMNS)) // Above Stack 7 (x = 300)
+    }
+}
+
+impl Default for WastePosition {
+    fn default() -> Self {
+        WastePosition(Layout::top_row_position(5, Layout::KLONDIKE_COLUMNS)) // Above Stack 6 (x = 200)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct ReservePosition(pub Option<Vec3>); // Canfield only: where the reserve sits
+
+/// A pile on the board
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PileId {
+    Stock,
+    Waste,
+    Foundation(usize),
+    FreeCell(usize),
+    Reserve, // Canfield
+    Tableau(usize),
+}
+
+#[derive(Resource)]
+pub struct FreeCells(pub Vec<Option<Entity>>); // FreeCell: the card parked in each free cell, if any
+
+#[derive(Resource)]
+pub struct FreeCellPositions(pub Vec<Vec3>);
+
+/// How ranks follow one another in the current game
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RankRules {
+    pub foundation_base: u8, // Rank that starts a foundation pile: Ace, or whatever Canfield deals first
+    pub wrap: bool,          // Whether King and Ace count as neighbours (Canfield)
+}
+
+impl Default for RankRules {
+    fn default() -> Self {
+        RankRules { foundation_base: 1, wrap: false }
+    }
+}
+
+impl RankRules {
+    /// Whether `card_value` is the rank right below `target_value`
+    pub fn is_one_below(&self, card_value: u8, target_value: u8) -> bool {
+        card_value + 1 == target_value || (self.wrap && card_value == 13 && target_value == 1)
+    }
+}
+
+/// Read-only access to where every pile of the current board sits
+#[derive(SystemParam)]
+pub struct PilePositions<'w> {
+    pub tableau: Res<'w, TableauPositions>,
+    pub foundations: Res<'w, FoundationPositions>,
+    pub free_cells: Res<'w, FreeCellPositions>,
+    pub stock: Res<'w, StockPosition>,
+    pub waste: Res<'w, WastePosition>,
+    pub reserve: Res<'w, ReservePosition>,
+}
+
+/// Tableau cards where they lie on the board, leaving out any being dragged
+pub type LyingTableauCards<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static CardData), (With<TableauPile>, Without<CurrentlyDragging>)>;
 
 #[derive(Resource)]
-pub struct UndoStack(pub Vec<UndoAction>); // Tracks undo actions
+pub struct UndoStack(pub Vec<UndoAction>); // The board as dealt, then after every move. The last one is the board in play.
 
+#[derive(Resource)]
+pub struct RedoStack(pub Vec<UndoAction>); // Undone boards, played back in reverse to redo them
+
+/// Sent by every system that makes a move, so the board is recorded for undo once it has settled
+#[derive(Message)]
+pub struct MoveMade;
+
+/// The whole board at one point in the game: every card, and the piles kept as data rather than as card positions
 #[derive(Clone)]
 pub struct UndoAction {
-    pub card_entity: Entity,
-    pub from_position: Vec3,
-    pub to_position: Vec3,
-    pub from_components: Vec<ComponentType>,
-    pub to_components: Vec<ComponentType>,
-    pub stack_cards: Vec<(Entity, Vec3)>, // For moving entire stacks
-    pub original_face_up: bool, // Track the original face up/down state
+    pub cards: Vec<CardState>,
+    pub stock_cards: Vec<CardId>,
+    pub foundation_piles: Vec<Vec<CardId>>,
+    pub free_cells: Vec<Option<Entity>>,
+    pub score: u32,
+    pub pyramid_board: Vec<Option<Entity>>, // Pyramid only
+    pub pyramid_discard: Vec<CardId>,
+    pub golf_cards: Vec<Option<Entity>>, // Golf and TriPeaks only
+    pub golf_streak: u32,
+}
+
+/// Where one card was, which way up, and which piles it belonged to
+#[derive(Clone)]
+pub struct CardState {
+    pub entity: Entity, // Cards dealt from the stock are despawned when the waste is recycled, so this may be gone
+    pub card_data: CardData,
+    pub position: Vec3,
+    pub original_position: Option<Vec3>,
+    pub components: Vec<ComponentType>,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ComponentType {
     TableauPile,
     WastePile,
@@ -120,6 +239,11 @@ pub enum ComponentType {
     Draggable,
     CardFront,
     CardBack,
+    FreeCellPile,
+    ReservePile,
+    SkippedWasteCard,
+    PyramidSlot(usize),
+    GolfSlot(usize),
 }
 
  
\ No newline at end of file
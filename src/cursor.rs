@@ -0,0 +1,645 @@
+use bevy::prelude::*;
+use crate::actions::GameAction;
+use crate::components::*;
+use crate::card_validation::*;
+use crate::card_placement::place_card;
+use crate::card_double_click::try_foundation_move_simple;
+use crate::feedback::{MoveFeedback, MoveRejected};
+use crate::ferris::{award_stack_points, send_full_stack};
+use crate::game_mode::GameMode;
+use crate::golf::{play_to_waste, GolfPlay};
+use crate::hit_test::board_piles;
+use crate::pyramid::{remove_pair, set_highlight, PyramidCards, PyramidPlay};
+use crate::sound::{PlaySound, SoundEffect};
+use crate::stock_click::{draw_from_stock, StockDeal};
+use crate::spider::spider_deal_row;
+use tracing::debug;
+
+const CURSOR_COLOR: Color = Color::srgba(1.0, 1.0, 0.3, 0.35);
+const CURSOR_HOLDING_COLOR: Color = Color::srgba(1.0, 0.5, 0.1, 0.45); // A card has been picked up
+
+/// Keyboard and gamepad play: which pile the cursor is on and which card, if any, has been picked up
+#[derive(Resource)]
+pub struct PileCursor {
+    pub pile: PileId,
+    pub depth: usize, // How far down a column's face-up run the cursor is, 0 being the top card
+    pub held: Option<(Entity, PileId)>, // The picked up card and the pile it came from
+    pub suggestion: usize, // Tab: which suggested target was picked last
+}
+
+impl Default for PileCursor {
+    fn default() -> Self {
+        PileCursor { pile: PileId::Tableau(0), depth: 0, held: None, suggestion: 0 }
+    }
+}
+
+/// The highlight drawn over the card or pile the cursor is on
+#[derive(Component)]
+pub struct CursorHighlight;
+
+/// Spawns the cursor highlight, hidden until the keyboard or a gamepad is used
+pub fn spawn_cursor_highlight(commands: &mut Commands) {
+    commands.spawn((
+        Sprite {
+            color: CURSOR_COLOR,
+            custom_size: Some(Vec2::new(90.0, 130.0)),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 100.0), // Above every card
+        Visibility::Hidden,
+        CursorHighlight,
+    ));
+}
+
+/// The cursor highlight, which is never a card
+type CursorHighlightQuery<'w, 's> = Query<'w, 's, (&'static mut Transform, &'static mut Sprite, &'static mut Visibility), (With<CursorHighlight>, Without<Card>)>;
+
+/// The waste and reserve cards that can be picked up, and which of the two each is in
+type LooseCardsQuery<'w, 's> = Query<'w, 's, (Entity, Has<ReservePile>), (With<Card>, With<Draggable>, Or<(With<WastePile>, With<ReservePile>)>)>;
+
+/// A row of piles and where each one sits
+type PileRow = Vec<(PileId, Vec3)>;
+
+/// The piles of the top row and of the tableau row, each ordered left to right, with where they sit
+fn cursor_rows(game_mode: GameMode, pile_positions: &PilePositions) -> [PileRow; 2] {
+    let (mut tableau_row, mut top_row): (PileRow, PileRow) = board_piles(game_mode, pile_positions)
+        .into_iter()
+        .partition(|(pile, _position)| matches!(pile, PileId::Tableau(_) | PileId::Reserve));
+
+    for row in [&mut top_row, &mut tableau_row] {
+        row.sort_by(|a, b| a.1.x.partial_cmp(&b.1.x).unwrap());
+    }
+    [top_row, tableau_row]
+}
+
+/// The pile in `row` closest to `x`
+fn nearest_pile(row: &[(PileId, Vec3)], x: f32) -> Option<PileId> {
+    row.iter()
+        .min_by(|a, b| (a.1.x - x).abs().partial_cmp(&(b.1.x - x).abs()).unwrap())
+        .map(|(pile, _position)| *pile)
+}
+
+/// The cards of a pile that the cursor can step through and pick up, top card first.
+/// For a column that is its face-up run; other piles only offer their top card.
+fn pile_cards(
+    pile: PileId,
+    pile_positions: &PilePositions,
+    free_cells: &FreeCells,
+    tableau_cards: &[(Entity, Vec3, CardData)],
+    loose_cards: &[(Entity, Vec3, bool)],
+    transform_query: &Query<&mut Transform, (With<Card>, With<Draggable>)>,
+) -> Vec<(Entity, Vec3)> {
+    match pile {
+        PileId::Tableau(i) => {
+            let Some(column) = pile_positions.tableau.0.get(i) else { return Vec::new() };
+            let mut cards: Vec<&(Entity, Vec3, CardData)> = tableau_cards
+                .iter()
+                .filter(|(_entity, position, _card_data)| (position.x - column.x).abs() < 15.0)
+                .collect();
+            cards.sort_by(|a, b| b.1.z.partial_cmp(&a.1.z).unwrap());
+            cards.into_iter()
+                .take_while(|(_entity, _position, card_data)| card_data.is_face_up)
+                .map(|(entity, position, _card_data)| (*entity, *position))
+                .collect()
+        }
+        PileId::Waste | PileId::Reserve => {
+            let want_reserve = pile == PileId::Reserve;
+            loose_cards
+                .iter()
+                .filter(|(_entity, _position, is_reserve)| *is_reserve == want_reserve)
+                .max_by(|a, b| a.1.z.partial_cmp(&b.1.z).unwrap())
+                .map(|(entity, position, _is_reserve)| (*entity, *position))
+                .into_iter()
+                .collect()
+        }
+        PileId::FreeCell(i) => free_cells.0
+            .get(i)
+            .copied()
+            .flatten()
+            .and_then(|entity| Some((entity, transform_query.get(entity).ok()?.translation)))
+            .into_iter()
+            .collect(),
+        // Foundation cards stay put and the stock is only dealt from
+        PileId::Foundation(_) | PileId::Stock => Vec::new(),
+    }
+}
+
+/// Cursor play for the drag-and-drop modes: moves the cursor between piles and up and down a column's
+/// face-up run, picks up and drops cards, sends them to a foundation and cycles through the places
+/// the held card can go
+pub fn cursor_play_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut feedback: MoveFeedback,
+    mut cursor: ResMut<PileCursor>,
+    play: CardPlay,
+    loose_cards_query: LooseCardsQuery,
+    mut cursor_query: CursorHighlightQuery,
+) {
+    let CardPlay { mut board, pile_positions, mut transform_query, card_data_query, entity_query, animating_query, tableau_cards_query } = play;
+    let Ok((mut cursor_transform, mut cursor_sprite, mut cursor_visibility)) = cursor_query.single_mut() else { return };
+    let game_mode = *board.rules.mode;
+    let rank_rules = *board.rules.ranks;
+
+    let action = take_cursor_action(&mut game_actions, &mut cursor, &mut cursor_visibility);
+    if *cursor_visibility == Visibility::Hidden {
+        return;
+    }
+
+    // Same view of the tableau as the drag and drop system
+    let tableau_cards: Vec<(Entity, Vec3, CardData)> = tableau_cards_query
+        .iter()
+        .filter_map(|(entity, card_data)| {
+            let transform = transform_query.get(entity).ok()?;
+            Some((entity, transform.translation, card_data.clone()))
+        })
+        .collect();
+    let loose_cards: Vec<(Entity, Vec3, bool)> = loose_cards_query
+        .iter()
+        .filter_map(|(entity, is_reserve)| Some((entity, transform_query.get(entity).ok()?.translation, is_reserve)))
+        .collect();
+    let [top_row, tableau_row] = cursor_rows(game_mode, &pile_positions);
+
+    // After a new deal or mode switch the pile may be gone
+    let in_top_row = top_row.iter().any(|(pile, _position)| *pile == cursor.pile);
+    if !in_top_row && !tableau_row.iter().any(|(pile, _position)| *pile == cursor.pile) {
+        cursor.pile = tableau_row.first().or(top_row.first()).map_or(PileId::Tableau(0), |(pile, _position)| *pile);
+        cursor.depth = 0;
+    }
+    let in_top_row = top_row.iter().any(|(pile, _position)| *pile == cursor.pile);
+    let row = if in_top_row { &top_row } else { &tableau_row };
+    let row_index = row.iter().position(|(pile, _position)| *pile == cursor.pile).unwrap_or(0);
+    let cards = pile_cards(cursor.pile, &pile_positions, &board.free_cells, &tableau_cards, &loose_cards, &transform_query);
+
+    // Where the cursor points in each pile: its top card, or the empty pile itself
+    let aims: Vec<(PileId, Vec3)> = top_row
+        .iter()
+        .chain(tableau_row.iter())
+        .map(|(pile, base)| {
+            let top = pile_cards(*pile, &pile_positions, &board.free_cells, &tableau_cards, &loose_cards, &transform_query).first().map(|(_entity, position)| *position);
+            (*pile, top.unwrap_or(*base))
+        })
+        .collect();
+    let pile_aim = |pile: PileId| aims.iter().find(|(other, _aim)| *other == pile).map(|(_pile, aim)| *aim);
+
+    match action {
+        Some(GameAction::Left) if row_index > 0 => {
+            cursor.pile = row[row_index - 1].0;
+            cursor.depth = 0;
+        }
+        Some(GameAction::Right) if row_index + 1 < row.len() => {
+            cursor.pile = row[row_index + 1].0;
+            cursor.depth = 0;
+        }
+        Some(GameAction::Up) => {
+            if !in_top_row && cursor.depth + 1 < cards.len() {
+                // Further down the face-up run, which is further up the screen
+                cursor.depth += 1;
+            } else if !in_top_row {
+                if let Some(pile) = nearest_pile(&top_row, row[row_index].1.x) {
+                    cursor.pile = pile;
+                    cursor.depth = 0;
+                }
+            }
+        }
+        Some(GameAction::Down) => {
+            if in_top_row {
+                if let Some(pile) = nearest_pile(&tableau_row, row[row_index].1.x) {
+                    cursor.pile = pile;
+                    cursor.depth = 0;
+                }
+            } else if cursor.depth > 0 {
+                cursor.depth -= 1;
+            }
+        }
+        Some(GameAction::Cancel) => {
+            debug!("Cursor: put the held card down");
+            cursor.held = None;
+        }
+        Some(GameAction::Select) => {
+            match cursor.held {
+                // Selecting the stock deals, which cursor_draw_system takes care of
+                _ if cursor.pile == PileId::Stock => {}
+                None => {
+                    if let Some((entity, _position)) = cards.get(cursor.depth) {
+                        if animating_query.contains(*entity) {
+                            debug!("Cursor: {:?} is still moving", entity);
+                        } else if can_drag_card(*entity, game_mode, &rank_rules, &entity_query, &transform_query, &card_data_query) {
+                            debug!("Cursor: picked up {:?} from {:?}", entity, cursor.pile);
+                            cursor.held = Some((*entity, cursor.pile));
+                            feedback.sounds.write(PlaySound(SoundEffect::PickUp));
+                            cursor.suggestion = 0;
+                        } else {
+                            debug!("Cursor: {:?} can't be picked up", entity);
+                        }
+                    }
+                }
+                Some((_held_entity, from_pile)) if from_pile == cursor.pile => {
+                    // Dropping a card back where it came from just puts it down
+                    cursor.held = None;
+                }
+                Some((held_entity, _from_pile)) => {
+                    let moving_cards: Vec<Entity> = std::iter::once(held_entity).chain(collect_cards_above(held_entity, &entity_query, &transform_query)).collect();
+                    let drop_target = pile_aim(cursor.pile).map_or(Err(MoveError::NoTarget), |aim| {
+                        find_valid_drop_target(aim.truncate(), &moving_cards, &board, &pile_positions, &tableau_cards, &card_data_query)
+                    });
+
+                    match drop_target {
+                        Ok(target) => {
+                            debug!("Cursor: dropping {:?} on {:?}", held_entity, target);
+                            let original_position = transform_query.get(held_entity).map_or(Vec3::ZERO, |transform| transform.translation);
+                            place_card(&mut commands, &mut board, &moving_cards, target, original_position, &card_data_query);
+                            feedback.sounds.write(PlaySound(SoundEffect::placed_on(target)));
+                            if let DropTarget::Tableau(_) = target {
+                                award_stack_points(game_mode, &mut board.game_score, moving_cards.len());
+                            }
+                            cursor.held = None;
+                            cursor.depth = 0;
+                        }
+                        Err(error) => {
+                            debug!("Cursor: {:?} can't go on {:?}: {:?}", held_entity, cursor.pile, error);
+                            feedback.rejections.write(MoveRejected { cards: moving_cards, error });
+                        }
+                    }
+                }
+            }
+        }
+        Some(GameAction::ToFoundation) if game_mode == GameMode::Ferris => {
+            // Ferris only takes full stacks: send the column's King to Ace stack home, as a click on it does
+            let column = match cursor.pile {
+                PileId::Tableau(i) => pile_positions.tableau.0.get(i).copied(),
+                _ => None,
+            };
+            if let Some(column) = column {
+                let mut column_cards: Vec<(Entity, Vec3, CardData)> = tableau_cards
+                    .iter()
+                    .filter(|(_entity, position, _card_data)| (position.x - column.x).abs() < 15.0)
+                    .cloned()
+                    .collect();
+                column_cards.sort_by(|a, b| a.1.z.partial_cmp(&b.1.z).unwrap());
+                if send_full_stack(&mut commands, &mut board, &pile_positions.foundations, &mut feedback.sounds, &column_cards) {
+                    debug!("Cursor: sent the full stack in {:?} to a foundation", cursor.pile);
+                    cursor.held = None;
+                    cursor.depth = 0;
+                }
+            }
+        }
+        Some(GameAction::ToFoundation) => {
+            // Send the top card under the cursor (or the held card) to a foundation
+            let card = cursor.held.map(|(entity, _from_pile)| entity).or(cards.first().map(|(entity, _position)| *entity));
+            if let Some(entity) = card {
+                let is_top_card = collect_cards_above(entity, &entity_query, &transform_query).is_empty();
+                if let (true, true, Ok(card_data)) = (game_mode.foundation_takes_single_cards(), is_top_card, card_data_query.get(entity)) {
+                    if try_foundation_move_simple(entity, &mut transform_query, card_data, &mut board, &pile_positions.foundations, &mut commands) {
+                        debug!("Cursor: sent {:?} to a foundation", entity);
+                        feedback.sounds.write(PlaySound(SoundEffect::Foundation));
+                        cursor.held = None;
+                        cursor.depth = 0;
+                    }
+                }
+            }
+        }
+        Some(GameAction::NextTarget) => {
+            // Jump to the next pile the held card could be dropped on
+            if let Some((held_entity, from_pile)) = cursor.held {
+                let moving_cards: Vec<Entity> = std::iter::once(held_entity).chain(collect_cards_above(held_entity, &entity_query, &transform_query)).collect();
+                let suggestions: Vec<PileId> = aims
+                    .iter()
+                    .filter(|(pile, _aim)| *pile != from_pile)
+                    .filter(|(_pile, aim)| {
+                        find_valid_drop_target(aim.truncate(), &moving_cards, &board, &pile_positions, &tableau_cards, &card_data_query).is_ok()
+                    })
+                    .map(|(pile, _aim)| *pile)
+                    .collect();
+
+                if suggestions.is_empty() {
+                    debug!("Cursor: nowhere to put {:?}", held_entity);
+                } else {
+                    cursor.suggestion = (cursor.suggestion + 1) % suggestions.len();
+                    cursor.pile = suggestions[cursor.suggestion];
+                    cursor.depth = 0;
+                }
+            }
+        }
+        // Drawing and undo are handled by their own systems
+        _ => {}
+    }
+
+    // Keep the highlight on the card or pile under the cursor
+    let cards = pile_cards(cursor.pile, &pile_positions, &board.free_cells, &tableau_cards, &loose_cards, &transform_query);
+    cursor.depth = cursor.depth.min(cards.len().saturating_sub(1));
+    let highlight = cards
+        .get(cursor.depth)
+        .map(|(_entity, position)| *position)
+        .or_else(|| top_row.iter().chain(tableau_row.iter()).find(|(pile, _base)| *pile == cursor.pile).map(|(_pile, base)| *base));
+    if let Some(position) = highlight {
+        cursor_transform.translation = Vec3::new(position.x, position.y, 100.0);
+    }
+    cursor_sprite.color = if cursor.held.is_some() { CURSOR_HOLDING_COLOR } else { CURSOR_COLOR };
+}
+
+/// The cursor action to play this frame, if any. The mouse (or a finger) hides the cursor and puts the
+/// held card down, and a cursor key or button shows it again. One action a frame, so each one sees the
+/// board the last one left behind.
+fn take_cursor_action(game_actions: &mut MessageReader<GameAction>, cursor: &mut PileCursor, visibility: &mut Visibility) -> Option<GameAction> {
+    for action in game_actions.read() {
+        match *action {
+            // The mouse (or a finger) takes over again as soon as it is used
+            GameAction::Press(_) => {
+                *visibility = Visibility::Hidden;
+                cursor.held = None;
+                return None;
+            }
+            // The pointer, quick move and undo systems look after these
+            GameAction::Drag(_) | GameAction::Drop(_) | GameAction::QuickMove(_) | GameAction::Undo | GameAction::Redo => {}
+            cursor_action => {
+                *visibility = Visibility::Visible;
+                return Some(cursor_action);
+            }
+        }
+    }
+    None
+}
+
+/// The stop nearest `from` in the direction of `step`, favouring the ones straight in line
+fn stop_towards(stops: &[(PileId, Vec3)], from: Vec3, step: Vec2) -> Option<PileId> {
+    stops
+        .iter()
+        .filter_map(|(pile, position)| {
+            let offset = (*position - from).truncate();
+            let along = offset.dot(step);
+            (along > 1.0).then_some((*pile, along + 2.0 * offset.perp_dot(step).abs()))
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(pile, _distance)| pile)
+}
+
+/// Moves the cursor between the stops of a board played by picking cards (Pyramid, Golf and TriPeaks).
+/// There the cursor is on `PileId::Tableau(slot)` for a card of the pyramid or peaks. The arrow keys go
+/// to the nearest stop that way, and once the card the cursor was on has been played it moves to the
+/// stop nearest `at`, where the highlight was.
+fn step_between_stops(cursor: &mut PileCursor, stops: &[(PileId, Vec3)], action: Option<GameAction>, at: Vec3) {
+    let from = match stops.iter().find(|(pile, _position)| *pile == cursor.pile) {
+        Some((_pile, position)) => *position,
+        None => {
+            let distance = |position: &Vec3| position.truncate().distance(at.truncate());
+            let Some((pile, position)) = stops.iter().min_by(|a, b| distance(&a.1).partial_cmp(&distance(&b.1)).unwrap()) else { return };
+            cursor.pile = *pile;
+            *position
+        }
+    };
+
+    let step = match action {
+        Some(GameAction::Left) => Vec2::NEG_X,
+        Some(GameAction::Right) => Vec2::X,
+        Some(GameAction::Up) => Vec2::Y,
+        Some(GameAction::Down) => Vec2::NEG_Y,
+        _ => return,
+    };
+    if let Some(pile) = stop_towards(stops, from, step) {
+        cursor.pile = pile;
+    }
+}
+
+/// Keeps the highlight on the stop the cursor is on
+fn show_cursor_at_stop(cursor: &PileCursor, stops: &[(PileId, Vec3)], transform: &mut Transform, sprite: &mut Sprite) {
+    if let Some((_pile, position)) = stops.iter().find(|(pile, _position)| *pile == cursor.pile) {
+        transform.translation = Vec3::new(position.x, position.y, 100.0);
+    }
+    sprite.color = if cursor.held.is_some() { CURSOR_HOLDING_COLOR } else { CURSOR_COLOR };
+}
+
+/// Cursor play for Pyramid: the arrow keys step between the cards that can be played, the waste and the
+/// stock. Select removes a King, or picks up a card and pairs it with the next one selected if the two
+/// add up to 13.
+pub fn cursor_pyramid_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut cursor: ResMut<PileCursor>,
+    mut play: PyramidPlay,
+    mut card_query: PyramidCards,
+    pile_positions: PilePositions,
+    mut cursor_query: CursorHighlightQuery,
+) {
+    let Ok((mut cursor_transform, mut cursor_sprite, mut cursor_visibility)) = cursor_query.single_mut() else { return };
+    let held = cursor.held.map(|(entity, _from_pile)| entity);
+    let action = take_cursor_action(&mut game_actions, &mut cursor, &mut cursor_visibility);
+    if *cursor_visibility == Visibility::Hidden {
+        set_highlight(&mut card_query, held, false);
+        return;
+    }
+
+    // Playable cards: exposed pyramid cards and the top waste card
+    let top_waste = card_query
+        .iter()
+        .filter(|(_entity, _transform, _card_data, _sprite, _slot, waste)| waste.is_some())
+        .max_by(|a, b| a.1.translation.z.partial_cmp(&b.1.translation.z).unwrap())
+        .map(|(entity, transform, ..)| (entity, transform.translation));
+    let mut stops: Vec<(PileId, Vec3)> = card_query
+        .iter()
+        .filter_map(|(entity, transform, _card_data, _sprite, slot, _waste)| {
+            let slot = slot?.0;
+            (play.board.0.get(slot) == Some(&Some(entity)) && play.board.is_exposed(slot)).then_some((PileId::Tableau(slot), transform.translation))
+        })
+        .collect();
+    stops.push((PileId::Waste, top_waste.map_or(pile_positions.waste.0, |(_entity, position)| position)));
+    stops.push((PileId::Stock, pile_positions.stock.0));
+    step_between_stops(&mut cursor, &stops, action, cursor_transform.translation);
+
+    let card_at = |pile: PileId| match pile {
+        PileId::Tableau(slot) => play.board.0.get(slot).copied().flatten(),
+        PileId::Waste => top_waste.map(|(entity, _position)| entity),
+        _ => None,
+    };
+
+    // A held card that has been covered by a new waste card can't be paired any more
+    if let Some((held_entity, from_pile)) = cursor.held {
+        if card_at(from_pile) != Some(held_entity) {
+            set_highlight(&mut card_query, Some(held_entity), false);
+            cursor.held = None;
+        }
+    }
+
+    match action {
+        Some(GameAction::Select) => {
+            if let Some(entity) = card_at(cursor.pile) {
+                let value = |entity: Entity| card_query.get(entity).map_or(0, |(_entity, _transform, card_data, ..)| card_data.value);
+                let held = cursor.held.take().map(|(held_entity, _from_pile)| (held_entity, value(held_entity)));
+                let selected_value = value(entity);
+                set_highlight(&mut card_query, held.map(|(held_entity, _value)| held_entity), false);
+
+                let pair = match held {
+                    // Kings go on their own
+                    _ if selected_value == 13 => Some(vec![entity]),
+                    // Selecting the held card again puts it down
+                    Some((held_entity, _value)) if held_entity == entity => None,
+                    Some((held_entity, held_value)) if held_value + selected_value == 13 => Some(vec![held_entity, entity]),
+                    // Otherwise the selected card is picked up as the first half of a pair
+                    _ => {
+                        debug!("Cursor: picked up {:?} to pair", entity);
+                        cursor.held = Some((entity, cursor.pile));
+                        set_highlight(&mut card_query, Some(entity), true);
+                        None
+                    }
+                };
+                if let Some(pair) = pair {
+                    debug!("Cursor: removing {:?}", pair);
+                    remove_pair(&mut commands, &mut play, &card_query, &pair);
+                }
+            }
+        }
+        Some(GameAction::Cancel) => {
+            set_highlight(&mut card_query, cursor.held.take().map(|(held_entity, _from_pile)| held_entity), false);
+        }
+        _ => {}
+    }
+
+    show_cursor_at_stop(&cursor, &stops, &mut cursor_transform, &mut cursor_sprite);
+}
+
+/// Cursor play for Golf and TriPeaks: the arrow keys step between the cards that can be played and the
+/// stock, and Select plays the card under the cursor onto the waste
+pub fn cursor_golf_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut cursor: ResMut<PileCursor>,
+    mut play: GolfPlay,
+    pile_positions: PilePositions,
+    mut cursor_query: CursorHighlightQuery,
+) {
+    let Ok((mut cursor_transform, mut cursor_sprite, mut cursor_visibility)) = cursor_query.single_mut() else { return };
+    let action = take_cursor_action(&mut game_actions, &mut cursor, &mut cursor_visibility);
+    if *cursor_visibility == Visibility::Hidden {
+        return;
+    }
+
+    let mut stops: Vec<(PileId, Vec3)> = (0..play.golf_board.cards.len())
+        .filter_map(|slot| {
+            let (_entity, transform, ..) = play.tableau_cards.get(play.playable_card(slot)?).ok()?;
+            Some((PileId::Tableau(slot), transform.translation))
+        })
+        .collect();
+    stops.push((PileId::Stock, pile_positions.stock.0));
+    step_between_stops(&mut cursor, &stops, action, cursor_transform.translation);
+
+    if let (Some(GameAction::Select), PileId::Tableau(slot)) = (action, cursor.pile) {
+        if let Some(entity) = play.playable_card(slot) {
+            debug!("Cursor: playing {:?} to the waste", entity);
+            play_to_waste(&mut commands, &mut play, entity);
+        }
+    }
+
+    show_cursor_at_stop(&cursor, &stops, &mut cursor_transform, &mut cursor_sprite);
+}
+
+/// Draw, or Select with the cursor on the stock, deals from the stock (a row of cards in Spider)
+pub fn cursor_draw_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    cursor: Res<PileCursor>,
+    mut deal: StockDeal,
+    game_mode: Res<GameMode>,
+    pile_positions: PilePositions,
+) {
+    let draws = game_actions
+        .read()
+        .filter(|action| **action == GameAction::Draw || (**action == GameAction::Select && cursor.pile == PileId::Stock))
+        .count();
+    if draws == 0 {
+        return;
+    }
+
+    debug!("Cursor: drawing from the stock");
+    if let GameMode::Spider(_) = *game_mode {
+        spider_deal_row(&mut commands, &mut deal, &pile_positions.tableau);
+    } else {
+        draw_from_stock(&mut commands, &mut deal, *game_mode, pile_positions.waste.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_atlas::CardAtlas;
+    use crate::game_mode::RankWrap;
+    use crate::golf::{GolfBoard, GolfSlot};
+
+    // Three cards of a pyramid row, one card of the row above them, and the stock far off to the right
+    fn stops() -> Vec<(PileId, Vec3)> {
+        vec![
+            (PileId::Tableau(0), Vec3::new(-90.0, 0.0, 1.0)),
+            (PileId::Tableau(1), Vec3::new(0.0, 0.0, 1.0)),
+            (PileId::Tableau(2), Vec3::new(90.0, 0.0, 1.0)),
+            (PileId::Tableau(3), Vec3::new(45.0, 55.0, 1.0)),
+            (PileId::Stock, Vec3::new(400.0, 250.0, 0.0)),
+        ]
+    }
+
+    #[test]
+    fn left_and_right_keep_to_the_row() {
+        let middle = Vec3::new(0.0, 0.0, 1.0);
+        assert_eq!(stop_towards(&stops(), middle, Vec2::X), Some(PileId::Tableau(2)));
+        assert_eq!(stop_towards(&stops(), middle, Vec2::NEG_X), Some(PileId::Tableau(0)));
+        assert_eq!(stop_towards(&stops(), middle, Vec2::Y), Some(PileId::Tableau(3)));
+        assert_eq!(stop_towards(&stops(), middle, Vec2::NEG_Y), None);
+    }
+
+    #[test]
+    fn a_cursor_on_a_played_card_moves_to_the_nearest_stop() {
+        let mut cursor = PileCursor { pile: PileId::Tableau(7), ..default() };
+        step_between_stops(&mut cursor, &stops(), None, Vec3::new(80.0, 10.0, 100.0));
+        assert_eq!(cursor.pile, PileId::Tableau(2));
+
+        step_between_stops(&mut cursor, &stops(), Some(GameAction::Up), Vec3::ZERO);
+        assert_eq!(cursor.pile, PileId::Tableau(3));
+        step_between_stops(&mut cursor, &stops(), Some(GameAction::Up), Vec3::ZERO);
+        assert_eq!(cursor.pile, PileId::Stock); // Nothing is above the top card but the stock
+    }
+
+    #[test]
+    fn the_cursor_plays_a_golf_card_onto_the_waste() {
+        let mut world = World::new();
+        world.insert_resource(GameMode::Golf);
+        world.insert_resource(RankWrap(false));
+        world.insert_resource(CardAtlas::empty());
+        world.insert_resource(GameScore(2));
+        world.insert_resource(PileCursor::default());
+        world.insert_resource(TableauPositions(Vec::new()));
+        world.insert_resource(FoundationPositions(Vec::new()));
+        world.insert_resource(FreeCellPositions(Vec::new()));
+        world.insert_resource(StockPosition::default());
+        world.insert_resource(WastePosition::default());
+        world.insert_resource(ReservePosition(None));
+        world.init_resource::<Messages<GameAction>>();
+        world.init_resource::<Messages<PlaySound>>();
+        world.init_resource::<Messages<MoveMade>>();
+        spawn_cursor_highlight(&mut world.commands());
+        world.flush();
+
+        // A 5 on the waste, and a 6 and a 9 left in the tableau
+        let card = |value: u8| CardData { suit: CardSuit::Hearts, value, deck: 0, is_face_up: true };
+        let waste = WastePosition::default().0 + Vec3::Z;
+        world.spawn((Card, card(5), Transform::from_translation(waste), WastePile));
+        let six = world.spawn((Card, card(6), Transform::from_xyz(-100.0, 0.0, 1.0), GolfSlot(0))).id();
+        let nine = world.spawn((Card, card(9), Transform::from_xyz(100.0, 0.0, 1.0), GolfSlot(1))).id();
+        world.insert_resource(GolfBoard { cards: vec![Some(six), Some(nine)], covered_by: vec![Vec::new(); 2], streak: 0 });
+        let cursor_golf = world.register_system(cursor_golf_system);
+
+        // The 9 doesn't go on the 5
+        world.write_message(GameAction::Right);
+        world.run_system(cursor_golf).unwrap();
+        world.write_message(GameAction::Select);
+        world.run_system(cursor_golf).unwrap();
+        assert_eq!(world.resource::<PileCursor>().pile, PileId::Tableau(1));
+        assert_eq!(world.resource::<GolfBoard>().cards_left(), 2);
+
+        world.write_message(GameAction::Left);
+        world.run_system(cursor_golf).unwrap();
+        world.write_message(GameAction::Select);
+        world.run_system(cursor_golf).unwrap();
+        assert_eq!(world.resource::<GolfBoard>().cards, vec![None, Some(nine)]);
+        assert!(world.entity(six).contains::<WastePile>());
+        assert_eq!(world.entity(six).get::<Transform>().unwrap().translation, waste + Vec3::Z);
+        assert_eq!(world.resource::<GameScore>().0, 1);
+    }
+}
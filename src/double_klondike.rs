@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::layout::Layout;
+use crate::setup::spawn_foundation_row;
+use crate::utils::{shuffle_deck, standard_deck};
+use crate::theme::CardImages;
+
+// Double Klondike: two decks, 9 columns and 8 foundations. The top row is too full for
+// Klondike's stock and waste spots, so they move right of the foundations.
+const DOUBLE_KLONDIKE_COLUMNS: usize = 9;
+const DOUBLE_KLONDIKE_FOUNDATIONS: usize = 8;
+const DOUBLE_KLONDIKE_TOP_ROW: usize = 10; // 8 foundations, then the waste and the stock
+
+/// Spawns the Double Klondike board: 104 cards, column `n` gets `n` cards with only the top one
+/// face-up (45 in all), and the other 59 go in the stock
+pub fn setup_double_klondike_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    let waste_position = Layout::top_row_position(8, DOUBLE_KLONDIKE_TOP_ROW); // x = 350
+    let stock_position = Layout::top_row_position(9, DOUBLE_KLONDIKE_TOP_ROW); // x = 450
+
+    // Both decks keep their own deck index so identical cards stay distinct
+    let mut deck = standard_deck(0);
+    deck.extend(standard_deck(1));
+    shuffle_deck(&mut deck, deal_number as u64);
+
+    spawn_foundation_row(commands, DOUBLE_KLONDIKE_TOP_ROW, DOUBLE_KLONDIKE_FOUNDATIONS);
+    commands.insert_resource(FoundationPiles(vec![Vec::new(); DOUBLE_KLONDIKE_FOUNDATIONS]));
+
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.3, 0.3, 0.3),
+            custom_size: Some(CARD_SIZE),
+            ..default()
+        },
+        Transform::from_translation(waste_position),
+        WastePile,
+    ));
+
+    let mut cards = deck.into_iter();
+    for column in 0..DOUBLE_KLONDIKE_COLUMNS {
+        let column_size = column + 1;
+
+        for row in 0..column_size {
+            let Some(card) = cards.next() else { break };
+            let position = Layout::tableau_position(column, DOUBLE_KLONDIKE_COLUMNS, row);
+
+            if row == column_size - 1 {
+                create_card_entity(
+                    commands,
+                    card_images,
+                    position,
+                    card,
+                    true,
+                    (
+                        Draggable,
+                        TableauPile,
+                        OriginalPosition(position),
+                    ),
+                );
+            } else {
+                create_card_entity(
+                    commands,
+                    card_images,
+                    position,
+                    card,
+                    false,
+                    (
+                        TableauPile,
+                        OriginalPosition(position),
+                        CardBack,
+                    ),
+                );
+            }
+        }
+    }
+
+    commands.insert_resource(StockCards(cards.collect()));
+    create_card_entity(
+        commands,
+        card_images,
+        stock_position,
+        CardId::new(0, CardSuit::Spades, 1), // Dummy card - not important for stock pile
+        false,
+        (
+            StockPile,
+            CardBack,
+        ),
+    );
+    commands.insert_resource(StockPosition(stock_position));
+    commands.insert_resource(WastePosition(waste_position));
+
+    let tableau_positions = (0..DOUBLE_KLONDIKE_COLUMNS)
+        .map(|column| Layout::tableau_position(column, DOUBLE_KLONDIKE_COLUMNS, 0))
+        .collect();
+    commands.insert_resource(TableauPositions(tableau_positions));
+}
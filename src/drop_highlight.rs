@@ -0,0 +1,115 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use crate::actions::GameAction;
+use crate::components::*;
+use crate::card_validation::{find_valid_drop_target, DropTarget};
+use crate::game_mode::BoardState;
+use crate::hit_test::{board_piles, pile_of};
+use tracing::debug;
+
+const TARGET_COLOR: Color = Color::srgba(0.3, 0.9, 1.0, 0.45);
+const TARGET_UNDER_POINTER_COLOR: Color = Color::srgba(0.4, 1.0, 0.4, 0.9); // The drop would land here
+const OUTLINE_WIDTH: f32 = 6.0;
+
+/// A frame drawn just behind the top card (or empty spot) of a pile the dragged cards can be dropped on
+#[derive(Component)]
+pub struct DropHighlight(pub DropTarget);
+
+/// Cards lying on the board, leaving out the ones being dragged
+type BoardCardsQuery<'w, 's> = Query<'w, 's, (Entity, &'static Transform), (With<Card>, Without<CurrentlyDragging>)>;
+
+/// Tableau cards with their data, as the drop validation sees them
+type TableauCardsQuery<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static CardData), (With<TableauPile>, Without<WastePile>)>;
+
+/// The board the dragged cards could land on: the rules, the piles and the cards lying in them
+#[derive(SystemParam)]
+pub struct DropBoard<'w, 's> {
+    board: BoardState<'w>,
+    pile_positions: PilePositions<'w>,
+    card_data_query: Query<'w, 's, &'static CardData>,
+    board_cards_query: BoardCardsQuery<'w, 's>,
+    tableau_cards_query: TableauCardsQuery<'w, 's>,
+}
+
+/// Outlines every legal destination while cards are being dragged, and brightens the one the
+/// pointer is over. The destinations are worked out once, when the drag starts.
+pub fn drop_highlight_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    selected_card: Res<SelectedCard>,
+    drop_board: DropBoard,
+    dragging_query: Query<(Entity, &Transform), With<CurrentlyDragging>>,
+    mut highlight_query: Query<(Entity, &DropHighlight, &mut Sprite)>,
+    mut highlighted_for: Local<Option<Entity>>,
+) {
+    let DropBoard { board, pile_positions, card_data_query, board_cards_query, tableau_cards_query } = drop_board;
+    let game_mode = *board.rules.mode;
+    let dragging = selected_card.0.filter(|entity| dragging_query.contains(*entity));
+    let drag_started = *highlighted_for != dragging;
+
+    // The drag ended (or a new one began): clear the old outlines
+    if drag_started {
+        for (entity, _highlight, _sprite) in highlight_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        *highlighted_for = dragging;
+    }
+    let Some(selected_entity) = dragging else { return };
+
+    // Carried cards, lowest first, as the drag and drop system fans them out
+    let mut dragged_stack: Vec<(Entity, f32)> = dragging_query
+        .iter()
+        .filter(|(entity, _transform)| *entity != selected_entity)
+        .map(|(entity, transform)| (entity, transform.translation.z))
+        .collect();
+    dragged_stack.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    let moving_cards: Vec<Entity> = std::iter::once(selected_entity).chain(dragged_stack.into_iter().map(|(entity, _z)| entity)).collect();
+
+    let tableau_cards: Vec<(Entity, Vec3, CardData)> = tableau_cards_query
+        .iter()
+        .map(|(entity, transform, card_data)| (entity, transform.translation, card_data.clone()))
+        .collect();
+    let find_target = |point: Vec2| {
+        find_valid_drop_target(point, &moving_cards, &board, &pile_positions, &tableau_cards, &card_data_query)
+    };
+
+    if drag_started {
+        // Try dropping on the top card (or the empty spot) of every pile that can take cards
+        let piles = board_piles(game_mode, &pile_positions);
+        let board_cards: Vec<(Entity, Vec3)> = board_cards_query.iter().map(|(entity, transform)| (entity, transform.translation)).collect();
+        for (pile, base) in piles.iter().filter(|(pile, _base)| matches!(pile, PileId::Tableau(_) | PileId::Foundation(_) | PileId::FreeCell(_))) {
+            let aim = board_cards
+                .iter()
+                .filter(|(_entity, position)| pile_of(*position, &piles) == Some(*pile))
+                .map(|(_entity, position)| *position)
+                .max_by(|a, b| a.z.partial_cmp(&b.z).unwrap())
+                .unwrap_or(*base);
+            let Ok(target) = find_target(aim.truncate()) else { continue };
+
+            debug!("Drop highlight: {:?} can go on {:?}", selected_entity, pile);
+            commands.spawn((
+                Sprite {
+                    color: TARGET_COLOR,
+                    custom_size: Some(CARD_SIZE + Vec2::splat(OUTLINE_WIDTH * 2.0)),
+                    ..default()
+                },
+                Transform::from_translation(aim - Vec3::Z * 0.5), // Just behind the card, so only the frame shows
+                DropHighlight(target),
+            ));
+        }
+        return; // The new outlines can be emphasised from next frame
+    }
+
+    // Emphasise the destination the cards would land on if dropped now
+    let Some(pointer) = game_actions.read().filter_map(|action| match action {
+        GameAction::Drag(position) => Some(*position),
+        _ => None,
+    }).last() else { return };
+    let under_pointer = find_target(pointer).ok();
+    for (_entity, highlight, mut sprite) in highlight_query.iter_mut() {
+        let color = if Some(highlight.0) == under_pointer { TARGET_UNDER_POINTER_COLOR } else { TARGET_COLOR };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
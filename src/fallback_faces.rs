@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+use crate::components::*;
+use crate::card_atlas::BACK_INDEX;
+use crate::theme::CardImages;
+use tracing::debug;
+
+const FONT: &str = "FiraSans-Bold.ttf";
+const RANK_LABELS: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+const RED: Color = Color::srgb(0.8, 0.1, 0.15);
+const BLACK: Color = Color::srgb(0.1, 0.1, 0.12);
+const BORDER_COLOR: Color = Color::srgb(0.35, 0.35, 0.4);
+const BORDER_WIDTH: f32 = 1.5;
+const RANK_FONT_SIZE: f32 = 18.0;
+const CORNER_SUIT_SIZE: f32 = 11.0;
+const MIDDLE_SUIT_SIZE: f32 = 34.0;
+const PART_Z: f32 = 0.1; // In front of the card, but well behind the next card up (a whole step in z)
+
+/// One piece of a suit symbol: a shape drawn one unit across, and where its middle goes in the symbol
+struct SuitPart {
+    mesh: Handle<Mesh>,
+    offset: Vec2,
+}
+
+/// Everything needed to draw a card face without its image: the font, the pieces of the four suit
+/// symbols (in CardSuit order) and the two ink colours
+#[derive(Resource)]
+pub struct FallbackFaceAssets {
+    font: Handle<Font>,
+    suits: [Vec<SuitPart>; 4],
+    red: Handle<ColorMaterial>,
+    black: Handle<ColorMaterial>,
+}
+
+/// A card face drawn with shapes and text because it has no image to show. `side` is the card atlas
+/// slot the drawing stands in for; once the card shows another slot the drawing is cleared away.
+#[derive(Component)]
+pub struct DrawnFace {
+    side: usize,
+}
+
+/// Builds the suit symbols out of circles and triangles, once, for every drawn face to share
+pub fn load_fallback_faces(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut part = |mesh: Mesh, x: f32, y: f32| SuitPart { mesh: meshes.add(mesh), offset: Vec2::new(x, y) };
+    let triangle = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| Mesh::from(Triangle2d::new(Vec2::from(a), Vec2::from(b), Vec2::from(c)));
+    let lobe = |radius: f32| Mesh::from(Circle::new(radius));
+
+    let hearts = vec![
+        part(lobe(0.27), -0.23, 0.18),
+        part(lobe(0.27), 0.23, 0.18),
+        part(triangle([-0.49, 0.1], [0.0, -0.5], [0.49, 0.1]), 0.0, 0.0),
+    ];
+    let diamonds = vec![part(Mesh::from(Rhombus::new(0.75, 1.0)), 0.0, 0.0)];
+    let clubs = vec![
+        part(lobe(0.22), 0.0, 0.24),
+        part(lobe(0.22), -0.24, -0.06),
+        part(lobe(0.22), 0.24, -0.06),
+        part(triangle([0.0, 0.05], [-0.18, -0.5], [0.18, -0.5]), 0.0, 0.0), // Stalk
+    ];
+    let spades = vec![
+        part(lobe(0.25), -0.22, -0.06),
+        part(lobe(0.25), 0.22, -0.06),
+        part(triangle([-0.46, -0.02], [0.46, -0.02], [0.0, 0.5]), 0.0, 0.0),
+        part(triangle([0.0, -0.1], [-0.18, -0.5], [0.18, -0.5]), 0.0, 0.0), // Stalk
+    ];
+
+    commands.insert_resource(FallbackFaceAssets {
+        font: asset_server.load(FONT),
+        suits: [hearts, diamonds, clubs, spades],
+        red: materials.add(RED),
+        black: materials.add(BLACK),
+    });
+}
+
+/// Draws the face of any card whose image is missing or failed to load, and of every card in a
+/// theme with drawn faces: the rank in two corners, the suit symbol under each and once more, large,
+/// in the middle. The card atlas leaves those faces plain white to draw on. (A missing back needs
+/// nothing here: the atlas holds a plain one.)
+pub fn fallback_faces_system(
+    mut commands: Commands,
+    card_images: CardImages,
+    fallback_assets: Res<FallbackFaceAssets>,
+    cards_query: Query<(Entity, &CardData, &Sprite, Option<&DrawnFace>), With<Card>>,
+) {
+    let atlas_changed = card_images.atlas.is_changed(); // A new deck theme may have the images the last one lacked
+    for (entity, card_data, sprite, drawn_face) in cards_query.iter() {
+        let Some(side) = sprite.texture_atlas.as_ref().map(|atlas| atlas.index) else { continue };
+        if let Some(drawn_face) = drawn_face {
+            if drawn_face.side == side && !atlas_changed {
+                continue; // Still drawn
+            }
+            // The card has turned over or the art has changed: clear the old drawing and look again
+            commands.entity(entity).despawn_related::<Children>().remove::<DrawnFace>();
+        }
+
+        if side == BACK_INDEX || !card_images.atlas.is_missing(side) {
+            continue;
+        }
+
+        debug!("No face image for the {} of {:?}, drawing one", RANK_LABELS[(card_data.value as usize).clamp(1, 13) - 1], card_data.suit);
+        commands.entity(entity)
+            .with_children(|parent| spawn_face(parent, card_data, &fallback_assets))
+            .insert(DrawnFace { side });
+    }
+}
+
+/// The shapes and text of a drawn card face, as children of the card
+fn spawn_face(parent: &mut ChildSpawnerCommands, card_data: &CardData, fallback_assets: &FallbackFaceAssets) {
+    let red = matches!(card_data.suit, CardSuit::Hearts | CardSuit::Diamonds);
+    let (color, material) = if red { (RED, &fallback_assets.red) } else { (BLACK, &fallback_assets.black) };
+    let label = RANK_LABELS[(card_data.value as usize).clamp(1, 13) - 1];
+
+    // A thin frame, so a white face stands out against the cards around it
+    parent.spawn((
+        Sprite {
+            color: BORDER_COLOR,
+            custom_size: Some(CARD_SIZE + Vec2::splat(BORDER_WIDTH * 2.0)),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, -0.05),
+    ));
+
+    // Rank and a small suit in the top left corner, and the same upside down in the bottom right
+    let corner = Vec2::new(-CARD_SIZE.x / 2.0 + 11.0, CARD_SIZE.y / 2.0 - 13.0);
+    for (position, rotation) in [(corner, Quat::IDENTITY), (-corner, Quat::from_rotation_z(std::f32::consts::PI))] {
+        let downwards = rotation * Vec3::NEG_Y;
+        parent.spawn((
+            Text2d::new(label),
+            TextFont { font: fallback_assets.font.clone(), font_size: RANK_FONT_SIZE, ..default() },
+            TextColor(color),
+            Transform::from_translation(position.extend(PART_Z)).with_rotation(rotation),
+        ));
+        let suit_position = position.extend(PART_Z) + downwards * 18.0;
+        spawn_suit(parent, card_data.suit, suit_position, rotation, CORNER_SUIT_SIZE, material, fallback_assets);
+    }
+
+    spawn_suit(parent, card_data.suit, Vec3::new(0.0, 0.0, PART_Z), Quat::IDENTITY, MIDDLE_SUIT_SIZE, material, fallback_assets);
+}
+
+fn spawn_suit(
+    parent: &mut ChildSpawnerCommands,
+    suit: CardSuit,
+    position: Vec3,
+    rotation: Quat,
+    size: f32,
+    material: &Handle<ColorMaterial>,
+    fallback_assets: &FallbackFaceAssets,
+) {
+    for part in &fallback_assets.suits[suit as usize] {
+        let offset = rotation * (part.offset * size).extend(0.0);
+        parent.spawn((
+            Mesh2d(part.mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(position + offset).with_rotation(rotation).with_scale(Vec3::new(size, size, 1.0)),
+        ));
+    }
+}
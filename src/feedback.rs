@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use crate::card_validation::MoveError;
+use crate::layout::ScreenAnchor;
+use crate::sound::PlaySound;
+use crate::tween::Shake;
+use tracing::debug;
+
+const TOAST_SECS: f32 = 2.5;
+const TOAST_FADE_SECS: f32 = 0.5; // At the end of its time on screen
+
+/// Cards the rules wouldn't let go where the player put them, and why. They have already been sent back.
+#[derive(Message, Clone, Debug)]
+pub struct MoveRejected {
+    pub cards: Vec<Entity>,
+    pub error: MoveError,
+}
+
+/// What a move made by the player sets off: a shake and a reason if it was turned down, and its sound
+#[derive(SystemParam)]
+pub struct MoveFeedback<'w> {
+    pub rejections: MessageWriter<'w, MoveRejected>,
+    pub sounds: MessageWriter<'w, PlaySound>,
+}
+
+/// Whether a turned-down move also shows the reason on screen. On by default to help new players; H toggles it.
+#[derive(Resource)]
+pub struct MoveHints(pub bool);
+
+/// A short message at the bottom of the window, such as the reason for the last turned-down move
+#[derive(Component)]
+pub struct Toast {
+    remaining: f32,
+}
+
+/// Shakes the cards of a turned-down move and, with hints on, explains why it wasn't allowed
+pub fn move_rejected_system(
+    mut commands: Commands,
+    mut move_rejections: MessageReader<MoveRejected>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut move_hints: ResMut<MoveHints>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyH) {
+        move_hints.0 = !move_hints.0;
+        debug!("Move hints {}", if move_hints.0 { "on" } else { "off" });
+    }
+
+    for rejection in move_rejections.read() {
+        debug!("Move rejected: {:?}", rejection.error);
+        for entity in &rejection.cards {
+            commands.entity(*entity).try_insert(Shake::default());
+        }
+
+        // Dropping cards on the felt needs no explanation
+        if !move_hints.0 || rejection.error == MoveError::NoTarget {
+            continue;
+        }
+
+        spawn_toast(&mut commands, rejection.error.reason());
+    }
+}
+
+/// Shows a short message at the bottom of the window for a moment. Only the latest one is shown.
+pub fn spawn_toast(commands: &mut Commands, text: String) {
+    commands.queue(|world: &mut World| {
+        let mut toasts = world.query_filtered::<Entity, With<Toast>>();
+        let old: Vec<Entity> = toasts.iter(world).collect();
+        for entity in old {
+            world.despawn(entity);
+        }
+    });
+    commands.spawn((
+        Text2d::new(text),
+        TextColor(Color::WHITE),
+        Transform::from_xyz(0.0, 0.0, 60.0),
+        ScreenAnchor { anchor: Vec2::new(0.0, -1.0), offset: Vec2::new(0.0, 40.0) }, // Bottom middle
+        Toast { remaining: TOAST_SECS },
+    ));
+}
+
+/// Fades the toast out at the end of its time and then removes it
+pub fn toast_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toast_query: Query<(Entity, &mut Toast, &mut TextColor)>,
+) {
+    for (entity, mut toast, mut text_color) in toast_query.iter_mut() {
+        toast.remaining -= time.delta_secs();
+        if toast.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+        } else if toast.remaining < TOAST_FADE_SECS {
+            text_color.0.set_alpha(toast.remaining / TOAST_FADE_SECS);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use crate::actions::GameAction;
+use crate::components::*;
+use crate::game_mode::{spawn_win_message, BoardState, GameMode};
+use crate::hit_test::{board_piles, card_under, pile_of};
+use crate::sound::{PlaySound, SoundEffect};
+use crate::utils::has_complete_stack;
+use tracing::debug;
+
+// Ferris scoring: stacking cards earns points, recycling the stock costs them
+pub const STACK_POINTS: u32 = 5;
+pub const FULL_STACK_POINTS: u32 = 100;
+pub const RECYCLE_PENALTY: u32 = 20;
+
+/// Awards points for cards stacked onto a tableau card (Ferris only)
+pub fn award_stack_points(game_mode: GameMode, game_score: &mut GameScore, cards_stacked: usize) {
+    if game_mode != GameMode::Ferris {
+        return;
+    }
+    game_score.0 += STACK_POINTS * cards_stacked as u32;
+    debug!("Stacked {} card(s), score is now {}", cards_stacked, game_score.0);
+}
+
+/// Takes the recycle penalty when the waste pile goes back into the stock (Ferris only)
+pub fn apply_recycle_penalty(game_mode: GameMode, game_score: &mut GameScore) {
+    if game_mode != GameMode::Ferris {
+        return;
+    }
+    game_score.0 = game_score.0.saturating_sub(RECYCLE_PENALTY);
+    debug!("Stock recycled, score is now {}", game_score.0);
+}
+
+/// Clicking a full King to Ace stack (alternating colours) sends it to the next empty foundation.
+/// In Ferris this is the only way cards reach the foundations.
+pub fn ferris_full_stack_click_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut sounds: MessageWriter<PlaySound>,
+    mut board: BoardState,
+    selected_card: Res<SelectedCard>,
+    pile_positions: PilePositions,
+    tableau_cards: LyingTableauCards,
+) {
+    let Some(cursor_world_pos) = game_actions.read().filter_map(GameAction::press_position).last() else { return };
+    if selected_card.0.is_some() {
+        return;
+    }
+
+    // Find the card the player sees under the cursor, and the column it is in
+    let piles = board_piles(GameMode::Ferris, &pile_positions);
+    let cards: Vec<(Entity, Vec3)> = tableau_cards
+        .iter()
+        .map(|(entity, transform, _card_data)| (entity, transform.translation))
+        .collect();
+    let Some(hit) = card_under(cursor_world_pos, &piles, &cards) else { return };
+
+    // The clicked column, lowest card first
+    let mut column_cards: Vec<(Entity, Vec3, CardData)> = tableau_cards
+        .iter()
+        .filter(|(_entity, transform, _card_data)| pile_of(transform.translation, &piles) == Some(hit.pile))
+        .map(|(entity, transform, card_data)| (entity, transform.translation, card_data.clone()))
+        .collect();
+    column_cards.sort_by(|a, b| a.1.z.partial_cmp(&b.1.z).unwrap());
+
+    // The clicked card has to be part of the stack
+    if hit.index + 13 < column_cards.len() {
+        return;
+    }
+    if send_full_stack(&mut commands, &mut board, &pile_positions.foundations, &mut sounds, &column_cards) {
+        debug!("Full stack clicked in {:?}", hit.pile);
+    }
+}
+
+/// Sends the top 13 cards of a column (given lowest card first) to the next empty foundation if they
+/// are a full King to Ace stack in alternating colours, and turns over the card the King sat on.
+/// Shared by the click and the pile cursor's send to foundation.
+pub fn send_full_stack(
+    commands: &mut Commands,
+    board: &mut BoardState,
+    foundation_positions: &FoundationPositions,
+    sounds: &mut MessageWriter<PlaySound>,
+    column_cards: &[(Entity, Vec3, CardData)],
+) -> bool {
+    if column_cards.len() < 13 {
+        return false;
+    }
+    let stack = &column_cards[column_cards.len() - 13..];
+    if !stack.iter().all(|(_entity, _position, card_data)| card_data.is_face_up) {
+        return false;
+    }
+    let stack_cards: Vec<CardId> = stack.iter().map(|(_entity, _position, card_data)| card_data.id()).collect();
+    if !has_complete_stack(&stack_cards) {
+        debug!("Column does not end in a full stack");
+        return false;
+    }
+
+    let Some(foundation_index) = board.foundation_piles.0.iter().position(|pile| pile.is_empty()) else { return false };
+    let Some(foundation_pos) = foundation_positions.0.get(foundation_index).copied() else { return false };
+    debug!("Moving a full stack to foundation {}", foundation_index);
+
+    for (i, (entity, _position, _card_data)) in stack.iter().enumerate() {
+        let new_position = Vec3::new(foundation_pos.x, foundation_pos.y, (i + 1) as f32);
+        commands.entity(*entity)
+            .insert(Transform::from_translation(new_position))
+            .insert(OriginalPosition(new_position))
+            .insert(FoundationPile)
+            .remove::<TableauPile>()
+            .remove::<Draggable>();
+    }
+    board.foundation_piles.0[foundation_index] = stack_cards;
+    board.game_score.0 += FULL_STACK_POINTS;
+    sounds.write(PlaySound(SoundEffect::Foundation));
+    board.moves.write(MoveMade);
+
+    // Turn over the card the King was sitting on
+    commands.spawn(NeedsFlipUnderneath(stack[0].1));
+    true
+}
+
+/// Ferris is won once every card is in play: stock and waste used up and nothing left face-down
+pub fn ferris_win_check_system(
+    mut commands: Commands,
+    stock_cards: Res<StockCards>,
+    waste_cards: Query<(), (With<WastePile>, With<Card>)>,
+    tableau_cards: Query<&CardData, With<TableauPile>>,
+    win_message_query: Query<(), With<WinMessage>>,
+) {
+    if !win_message_query.is_empty() || !stock_cards.0.is_empty() || !waste_cards.is_empty() {
+        return;
+    }
+
+    if tableau_cards.iter().any(|card_data| !card_data.is_face_up) {
+        return;
+    }
+
+    debug!("Every card is in play - Ferris game won");
+    spawn_win_message(&mut commands);
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::layout::Layout;
+use crate::theme::CardImages;
+
+// FreeCell board: 8 columns, 4 free cells on the left of the top row, 4 foundations on the right
+const FREECELL_COLUMNS: usize = 8;
+const FREECELL_CELLS: usize = 4;
+
+/// Reproduces the classic Microsoft FreeCell deal for the given deal number (1-32000).
+/// Cards come out in dealing order: card `i` goes to column `i % 8`, row `i / 8`.
+pub fn ms_deal(deal_number: u32) -> Vec<CardId> {
+    // Microsoft C runtime rand(): 15-bit results from a 31-bit LCG
+    let mut seed = deal_number;
+    let mut rand = move || {
+        seed = seed.wrapping_mul(214013).wrapping_add(2531011) & 0x7fff_ffff;
+        seed >> 16
+    };
+
+    // Cards are numbered 0-51 as rank * 4 + suit with suits in Clubs, Diamonds, Hearts, Spades order.
+    // The deck starts reversed (KS first) and each step swaps in a random card from the untouched tail.
+    let mut deck: Vec<u32> = (0..52).rev().collect();
+    for i in 0..51 {
+        let j = 51 - (rand() % (52 - i as u32)) as usize;
+        deck.swap(i, j);
+    }
+
+    let suits = [CardSuit::Clubs, CardSuit::Diamonds, CardSuit::Hearts, CardSuit::Spades];
+    deck.iter()
+        .map(|card| CardId::new(0, suits[(card % 4) as usize], (card / 4 + 1) as u8))
+        .collect()
+}
+
+/// How many cards can be moved as a group with the help of empty free cells and columns.
+/// Moving onto an empty column means that column can't also be used as temporary space,
+/// so callers should leave it out of `empty_columns`.
+pub fn supermove_limit(empty_free_cells: usize, empty_columns: usize) -> usize {
+    (empty_free_cells + 1) * (1 << empty_columns)
+}
+
+/// Counts the tableau columns with no cards in them, ignoring the cards being moved
+pub fn count_empty_columns(
+    tableau_positions: &[Vec3],
+    tableau_cards: &[(Entity, Vec3, CardData)],
+    moving_cards: &[Entity],
+) -> usize {
+    tableau_positions
+        .iter()
+        .filter(|column| {
+            !tableau_cards.iter().any(|(entity, position, _card_data)| {
+                !moving_cards.contains(entity) && (position.x - column.x).abs() < 5.0
+            })
+        })
+        .count()
+}
+
+/// Finds an empty free cell near the cursor
+pub fn find_free_cell_target(
+    cursor_pos: Vec2,
+    free_cells: &FreeCells,
+    free_cell_positions: &FreeCellPositions,
+) -> Option<(usize, Vec3)> {
+    for (i, cell_pos) in free_cell_positions.0.iter().enumerate() {
+        let distance = (cursor_pos - cell_pos.truncate()).length();
+        if distance < 80.0 && free_cells.0.get(i).is_some_and(|slot| slot.is_none()) {
+            return Some((i, *cell_pos));
+        }
+    }
+
+    None
+}
+
+/// Spawns the FreeCell board and deals all 52 cards face-up into 8 columns
+pub fn setup_freecell_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    // Free cells on the left half of the top row, over the first 4 columns
+    let mut free_cell_positions = Vec::new();
+    for i in 0..FREECELL_CELLS {
+        let position = Layout::top_row_position(i, FREECELL_COLUMNS);
+        commands.spawn((
+            Sprite {
+                color: Color::srgb(0.25, 0.25, 0.3),
+                custom_size: Some(CARD_SIZE),
+                ..default()
+            },
+            Transform::from_translation(position),
+            FreeCellPile,
+        ));
+        free_cell_positions.push(position);
+    }
+
+    // Foundation piles on the right half of the top row
+    let mut foundation_positions = Vec::new();
+    for i in 0..4 {
+        let position = Layout::top_row_position(FREECELL_CELLS + i, FREECELL_COLUMNS);
+        commands.spawn((
+            Sprite {
+                color: Color::srgb(0.2, 0.2, 0.2),
+                custom_size: Some(CARD_SIZE),
+                ..default()
+            },
+            Transform::from_translation(position),
+            FoundationPile,
+        ));
+        foundation_positions.push(position);
+    }
+
+    // Deal row by row, left to right, exactly as the Microsoft game does
+    for (i, card) in ms_deal(deal_number).into_iter().enumerate() {
+        let column = i % FREECELL_COLUMNS;
+        let row = i / FREECELL_COLUMNS;
+        let position = Layout::tableau_position(column, FREECELL_COLUMNS, row);
+
+        create_card_entity(
+            commands,
+            card_images,
+            position,
+            card,
+            true, // Every card is dealt face-up
+            (
+                Draggable,
+                TableauPile,
+                CardFront,
+                OriginalPosition(position),
+            ),
+        );
+    }
+
+    let tableau_positions = (0..FREECELL_COLUMNS)
+        .map(|column| Layout::tableau_position(column, FREECELL_COLUMNS, 0))
+        .collect();
+
+    commands.insert_resource(TableauPositions(tableau_positions));
+    commands.insert_resource(FoundationPositions(foundation_positions));
+    commands.insert_resource(FreeCellPositions(free_cell_positions));
+    commands.insert_resource(FreeCells(vec![None; FREECELL_CELLS]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cards written the way published FreeCell deals are, e.g. "TD" for the Ten of Diamonds
+    fn card_names(cards: &[CardId]) -> String {
+        cards
+            .iter()
+            .map(|card| {
+                let rank = "A23456789TJQK".as_bytes()[card.value as usize - 1] as char;
+                let suit = match card.suit {
+                    CardSuit::Clubs => 'C',
+                    CardSuit::Diamonds => 'D',
+                    CardSuit::Hearts => 'H',
+                    CardSuit::Spades => 'S',
+                };
+                format!("{}{}", rank, suit)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn deal_1_matches_the_microsoft_game() {
+        // Row by row, left to right, as the game lays them out
+        let expected = "JD 2D 9H JC 5D 7H 7C 5H \
+                        KD KC 9S 5S AD QC KH 3H \
+                        2S KS 9D QD JS AS AH 3C \
+                        4C 5C TS QH 4H AC 4D 7S \
+                        3S TD 4S TH 8H 2C JH 7D \
+                        6D 8S 8D QS 6C 3D 8C TC \
+                        6S 9C 2H 6H";
+        assert_eq!(card_names(&ms_deal(1)), expected);
+    }
+
+    #[test]
+    fn deal_617_matches_the_microsoft_game() {
+        let expected = "7D AD 5C 3S 5S 8C 2D AH \
+                        TD 7S QD AC 6D 8H AS KH \
+                        TH QC 3H 9D 6S 8D 3D TC \
+                        KD 5H 9S 3C 8S 7H 4D JS \
+                        4C QS 9C 9H 7C 6H 2C 2S \
+                        4S TS 2H 5D JC 6C JH QH \
+                        JD KS KC 4H";
+        assert_eq!(card_names(&ms_deal(617)), expected);
+    }
+
+    #[test]
+    fn every_deal_is_a_full_deck() {
+        for deal_number in [1, 617, 11982, 32000] {
+            let mut cards = ms_deal(deal_number);
+            assert_eq!(cards.len(), 52);
+            cards.sort_by_key(|card| (card.suit as u8, card.value));
+            cards.dedup();
+            assert_eq!(cards.len(), 52, "deal {} repeats a card", deal_number);
+        }
+    }
+
+    #[test]
+    fn supermove_limit_doubles_with_each_empty_column() {
+        assert_eq!(supermove_limit(0, 0), 1);
+        assert_eq!(supermove_limit(4, 0), 5);
+        assert_eq!(supermove_limit(0, 1), 2);
+        assert_eq!(supermove_limit(1, 1), 4);
+        assert_eq!(supermove_limit(2, 2), 12);
+        assert_eq!(supermove_limit(4, 3), 40);
+    }
+}
@@ -0,0 +1,376 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use crate::components::*;
+use crate::layout::ScreenAnchor;
+use crate::setup::setup_board;
+use crate::card_validation::MoveError;
+use crate::utils::{can_place_on_card, check_tableau_card, RunRule};
+use crate::theme::CardImages;
+use tracing::debug;
+
+/// The solitaire variant being played.
+/// Picked with `--mode <name>` on the command line, or cycled in-game with M.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    #[default]
+    Klondike,
+    DoubleKlondike, // Two decks: 9 columns and 8 foundations
+    Ferris, // House rules: only full King to Ace stacks go to the foundations
+    FreeCell,
+    Spider(SpiderSuits),
+    Pyramid,
+    Golf,
+    TriPeaks,
+    Yukon,
+    Russian,
+    Canfield,
+}
+
+/// Spider difficulty: how many different suits the two decks are made of
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpiderSuits {
+    One,
+    Two,
+    Four,
+}
+
+impl SpiderSuits {
+    /// The suits used, each repeated until there are 104 cards
+    pub fn suits(self) -> &'static [CardSuit] {
+        match self {
+            SpiderSuits::One => &[CardSuit::Spades],
+            SpiderSuits::Two => &[CardSuit::Spades, CardSuit::Hearts],
+            SpiderSuits::Four => &[CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Clubs, CardSuit::Spades],
+        }
+    }
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 13] = [
+        GameMode::Klondike,
+        GameMode::DoubleKlondike,
+        GameMode::Ferris,
+        GameMode::FreeCell,
+        GameMode::Spider(SpiderSuits::One),
+        GameMode::Spider(SpiderSuits::Two),
+        GameMode::Spider(SpiderSuits::Four),
+        GameMode::Pyramid,
+        GameMode::Golf,
+        GameMode::TriPeaks,
+        GameMode::Yukon,
+        GameMode::Russian,
+        GameMode::Canfield,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Klondike => "Klondike",
+            GameMode::DoubleKlondike => "Double Klondike",
+            GameMode::Ferris => "Ferris (full stacks)",
+            GameMode::FreeCell => "FreeCell",
+            GameMode::Spider(SpiderSuits::One) => "Spider (1 suit)",
+            GameMode::Spider(SpiderSuits::Two) => "Spider (2 suits)",
+            GameMode::Spider(SpiderSuits::Four) => "Spider (4 suits)",
+            GameMode::Pyramid => "Pyramid",
+            GameMode::Golf => "Golf",
+            GameMode::TriPeaks => "TriPeaks",
+            GameMode::Yukon => "Yukon",
+            GameMode::Russian => "Russian Solitaire",
+            GameMode::Canfield => "Canfield",
+        }
+    }
+
+    /// Short name used with `--mode`
+    pub fn id(self) -> &'static str {
+        match self {
+            GameMode::Klondike => "klondike",
+            GameMode::DoubleKlondike => "double",
+            GameMode::Ferris => "ferris",
+            GameMode::FreeCell => "freecell",
+            GameMode::Spider(SpiderSuits::One) => "spider1",
+            GameMode::Spider(SpiderSuits::Two) => "spider2",
+            GameMode::Spider(SpiderSuits::Four) => "spider4",
+            GameMode::Pyramid => "pyramid",
+            GameMode::Golf => "golf",
+            GameMode::TriPeaks => "tripeaks",
+            GameMode::Yukon => "yukon",
+            GameMode::Russian => "russian",
+            GameMode::Canfield => "canfield",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL
+            .into_iter()
+            .find(|mode| mode.id().eq_ignore_ascii_case(name) || mode.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The mode that follows this one when cycling with M
+    pub fn next(self) -> GameMode {
+        let index = GameMode::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        GameMode::ALL[(index + 1) % GameMode::ALL.len()]
+    }
+
+    /// Whether a card may be moved onto an empty tableau column
+    pub fn can_fill_empty_column(self, card_data: &CardData) -> bool {
+        self.check_fill_empty_column(card_data).is_ok()
+    }
+
+    /// Checks a card against an empty tableau column, saying why it can't go there
+    pub fn check_fill_empty_column(self, card_data: &CardData) -> Result<(), MoveError> {
+        match self {
+            GameMode::Klondike | GameMode::DoubleKlondike | GameMode::Ferris | GameMode::Yukon | GameMode::Russian => {
+                if card_data.value == 13 { Ok(()) } else { Err(MoveError::KingsOnly) } // Only Kings
+            }
+            GameMode::FreeCell | GameMode::Spider(_) | GameMode::Canfield => Ok(()),
+            GameMode::Pyramid | GameMode::Golf | GameMode::TriPeaks => Err(MoveError::NoTarget), // Cards never move back onto the tableau
+        }
+    }
+
+    /// How the cards of a run must relate for the run to be moved as a group
+    pub fn run_rule(self) -> RunRule {
+        match self {
+            GameMode::Spider(_) => RunRule::SameSuit,
+            GameMode::Yukon | GameMode::Russian => RunRule::Any,
+            _ => RunRule::AlternatingColors,
+        }
+    }
+
+    /// Whether `card_data` may be placed on the tableau card `target_card`
+    pub fn can_build_on_tableau(self, card_data: &CardData, target_card: &CardData, rules: &RankRules) -> bool {
+        self.check_build_on_tableau(card_data, target_card, rules).is_ok()
+    }
+
+    /// Checks `card_data` against the tableau card `target_card`, saying why it can't go there
+    pub fn check_build_on_tableau(self, card_data: &CardData, target_card: &CardData, rules: &RankRules) -> Result<(), MoveError> {
+        match self {
+            GameMode::Klondike | GameMode::DoubleKlondike | GameMode::Ferris | GameMode::FreeCell | GameMode::Yukon | GameMode::Canfield => {
+                check_tableau_card(card_data, target_card, rules)
+            }
+            // Russian builds down in suit, Spider builds down regardless of suit
+            GameMode::Russian | GameMode::Spider(_) => {
+                if !target_card.is_face_up {
+                    Err(MoveError::FaceDown)
+                } else if self == GameMode::Russian && card_data.suit != target_card.suit {
+                    Err(MoveError::WrongSuit)
+                } else if !can_place_on_card(card_data.value, target_card.value, rules) {
+                    Err(MoveError::WrongRank)
+                } else {
+                    Ok(())
+                }
+            }
+            // Pyramid pairs cards up and Golf/TriPeaks play to the waste instead of building
+            GameMode::Pyramid | GameMode::Golf | GameMode::TriPeaks => Err(MoveError::NoTarget),
+        }
+    }
+
+    /// Whether single cards can be played to the foundations (Ferris and Spider only take complete runs,
+    /// Pyramid only discards pairs, Golf and TriPeaks have no foundations)
+    pub fn foundation_takes_single_cards(self) -> bool {
+        matches!(self, GameMode::Klondike | GameMode::DoubleKlondike | GameMode::FreeCell | GameMode::Yukon | GameMode::Russian | GameMode::Canfield)
+    }
+
+    /// Whether the board is laid out like Klondike, with a stock dealing one card at a time to a waste pile
+    pub fn has_waste_pile(self) -> bool {
+        matches!(self, GameMode::Klondike | GameMode::DoubleKlondike | GameMode::Ferris | GameMode::Pyramid | GameMode::Golf | GameMode::TriPeaks | GameMode::Canfield)
+    }
+
+    /// How many cards each click on the stock turns over (Canfield deals three at a time)
+    pub fn draw_count(self) -> usize {
+        if self == GameMode::Canfield { 3 } else { 1 }
+    }
+
+    /// Whether the waste goes back into the stock once the stock runs out (Golf and TriPeaks get one pass)
+    pub fn recycles_stock(self) -> bool {
+        !matches!(self, GameMode::Golf | GameMode::TriPeaks)
+    }
+
+    /// Whether cards are moved by dragging them between tableau piles (the other modes have their own input)
+    pub fn uses_tableau_drag(self) -> bool {
+        matches!(self, GameMode::Klondike | GameMode::DoubleKlondike | GameMode::Ferris | GameMode::FreeCell | GameMode::Spider(_) | GameMode::Yukon | GameMode::Russian | GameMode::Canfield)
+    }
+}
+
+/// Run condition: the board has a Klondike stock and waste pile
+pub fn has_waste_pile(game_mode: Res<GameMode>) -> bool {
+    game_mode.has_waste_pile()
+}
+
+/// The mode together with the rank rules of the current deal
+#[derive(SystemParam)]
+pub struct GameRules<'w> {
+    pub mode: Res<'w, GameMode>,
+    pub ranks: Res<'w, RankRules>,
+}
+
+/// The rules in play and the piles the board keeps as data rather than as card positions:
+/// what a move is checked against, and what it updates, the score included. A move made is sent on for undo.
+#[derive(SystemParam)]
+pub struct BoardState<'w> {
+    pub rules: GameRules<'w>,
+    pub foundation_piles: ResMut<'w, FoundationPiles>,
+    pub free_cells: ResMut<'w, FreeCells>,
+    pub game_score: ResMut<'w, GameScore>,
+    pub moves: MessageWriter<'w, MoveMade>,
+}
+
+/// Run condition: cards are dragged between tableau piles
+pub fn uses_tableau_drag(game_mode: Res<GameMode>) -> bool {
+    game_mode.uses_tableau_drag()
+}
+
+/// Run condition: a Ferris (full stacks) game is being played
+pub fn is_ferris(game_mode: Res<GameMode>) -> bool {
+    *game_mode == GameMode::Ferris
+}
+
+/// Run condition: a Spider game is being played
+pub fn is_spider(game_mode: Res<GameMode>) -> bool {
+    matches!(*game_mode, GameMode::Spider(_))
+}
+
+/// Run condition: a Pyramid game is being played
+pub fn is_pyramid(game_mode: Res<GameMode>) -> bool {
+    *game_mode == GameMode::Pyramid
+}
+
+/// Run condition: a Golf or TriPeaks game is being played
+pub fn is_golf_or_tripeaks(game_mode: Res<GameMode>) -> bool {
+    matches!(*game_mode, GameMode::Golf | GameMode::TriPeaks)
+}
+
+/// Number of the current deal. For FreeCell this is the classic Microsoft deal number (1-32000),
+/// for the other seeded modes it's the shuffle seed.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DealNumber(pub u32);
+
+impl DealNumber {
+    pub const MAX: u32 = 32000;
+
+    pub fn random() -> DealNumber {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or(0);
+        DealNumber(nanos % DealNumber::MAX + 1)
+    }
+}
+
+/// Golf and TriPeaks: whether Kings and Aces count as adjacent ranks
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RankWrap(pub bool);
+
+/// Reads `--mode <name>` (e.g. `freecell`, `spider2`), `--deal <number>` and `--wrap` from the command line
+pub fn parse_command_line() -> (GameMode, DealNumber, RankWrap) {
+    let mut game_mode = GameMode::default();
+    let mut deal_number = DealNumber::random();
+    let mut rank_wrap = RankWrap::default();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--mode" => {
+                if let Some(mode) = args.get(i + 1).and_then(|name| GameMode::from_name(name)) {
+                    game_mode = mode;
+                }
+                i += 1;
+            }
+            "--deal" => {
+                if let Some(deal) = args.get(i + 1).and_then(|deal| deal.parse::<u32>().ok()) {
+                    deal_number = DealNumber(deal.clamp(1, DealNumber::MAX));
+                }
+                i += 1;
+            }
+            "--wrap" => rank_wrap = RankWrap(true),
+            _ => {}
+        }
+        i += 1;
+    }
+
+    (game_mode, deal_number, rank_wrap)
+}
+
+pub fn mode_label_text(game_mode: GameMode, deal_number: DealNumber) -> String {
+    match game_mode {
+        GameMode::Klondike | GameMode::Ferris => game_mode.name().to_string(),
+        _ => format!("{} #{}", game_mode.name(), deal_number.0),
+    }
+}
+
+/// Everything a new game clears away: cards, pile placeholders, pending flip triggers and the win message
+type OnBoard = Or<(With<Card>, With<TableauPile>, With<FoundationPile>, With<WastePile>, With<StockPile>, With<FreeCellPile>, With<NeedsFlipUnderneath>, With<WinMessage>)>;
+
+/// Starts a new game: N deals again in the current mode, M switches to the next mode
+pub fn new_game_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    card_images: CardImages,
+    mut game_mode: ResMut<GameMode>,
+    mut deal_number: ResMut<DealNumber>,
+    board_query: Query<Entity, OnBoard>,
+    mut mode_label_query: Query<&mut Text2d, With<ModeLabel>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        *game_mode = game_mode.next();
+    } else if !keyboard_input.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+
+    *deal_number = DealNumber::random();
+    debug!("Starting new {:?} game, deal {}", *game_mode, deal_number.0);
+
+    // Clear the old board (cards, pile placeholders and any pending flip triggers)
+    for entity in board_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    setup_board(&mut commands, &card_images, *game_mode, *deal_number);
+
+    for mut text in mode_label_query.iter_mut() {
+        text.0 = mode_label_text(*game_mode, *deal_number);
+    }
+}
+
+/// Shows a win message once every foundation pile is complete
+pub fn win_check_system(
+    mut commands: Commands,
+    foundation_piles: Res<FoundationPiles>,
+    win_message_query: Query<(), With<WinMessage>>,
+) {
+    if !foundation_piles.is_changed() || !win_message_query.is_empty() {
+        return;
+    }
+
+    if foundation_piles.0.is_empty() || !foundation_piles.0.iter().all(|pile| pile.len() == 13) {
+        return;
+    }
+
+    debug!("All foundation piles complete - game won");
+    spawn_win_message(&mut commands);
+}
+
+pub fn spawn_win_message(commands: &mut Commands) {
+    commands.spawn((
+        Text2d::new("You win! Press N for a new game"),
+        Transform::from_xyz(0.0, 0.0, 50.0),
+        ScreenAnchor { anchor: Vec2::ZERO, offset: Vec2::ZERO }, // Middle of the window
+        WinMessage,
+    ));
+}
+
+/// Keeps the score text in sync with the GameScore resource
+pub fn score_display_system(
+    game_score: Res<GameScore>,
+    mut score_query: Query<&mut Text2d, With<Score>>,
+) {
+    if !game_score.is_changed() {
+        return;
+    }
+
+    for mut text in score_query.iter_mut() {
+        text.0 = format!("Score: {}", game_score.0);
+    }
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use std::collections::HashSet;
+use crate::actions::GameAction;
+
+// How far the left stick has to be pushed to move the cursor, and how far back it has to come before it moves again
+const STICK_PRESS: f32 = 0.6;
+const STICK_RELEASE: f32 = 0.3;
+
+/// Gamepad bindings: D-pad or left stick moves the pile cursor, A picks up and drops, B cancels,
+/// X draws from the stock, Y sends to a foundation, the shoulder buttons undo and redo
+pub fn gamepad_input_system(
+    gamepads: Query<(Entity, &Gamepad)>,
+    mut game_actions: MessageWriter<GameAction>,
+    mut sticks_pushed: Local<HashSet<Entity>>, // Gamepads whose stick is pushed and has moved the cursor
+) {
+    let bindings = [
+        (GamepadButton::DPadLeft, GameAction::Left),
+        (GamepadButton::DPadRight, GameAction::Right),
+        (GamepadButton::DPadUp, GameAction::Up),
+        (GamepadButton::DPadDown, GameAction::Down),
+        (GamepadButton::South, GameAction::Select),
+        (GamepadButton::East, GameAction::Cancel),
+        (GamepadButton::West, GameAction::Draw),
+        (GamepadButton::North, GameAction::ToFoundation),
+        (GamepadButton::LeftTrigger, GameAction::Undo), // The shoulder buttons (the triggers are LeftTrigger2 and RightTrigger2)
+        (GamepadButton::RightTrigger, GameAction::Redo),
+    ];
+
+    // Forget gamepads that have been unplugged
+    sticks_pushed.retain(|entity| gamepads.contains(*entity));
+
+    for (entity, gamepad) in gamepads.iter() {
+        for (button, action) in bindings {
+            if gamepad.just_pressed(button) {
+                game_actions.write(action);
+            }
+        }
+
+        // The stick moves the cursor one pile per push, like the D-pad
+        let stick = gamepad.left_stick();
+        let stick_pushed = sticks_pushed.contains(&entity);
+        if !stick_pushed && stick.length() > STICK_PRESS {
+            sticks_pushed.insert(entity);
+            let action = if stick.x.abs() > stick.y.abs() {
+                if stick.x < 0.0 { GameAction::Left } else { GameAction::Right }
+            } else if stick.y > 0.0 {
+                GameAction::Up
+            } else {
+                GameAction::Down
+            };
+            game_actions.write(action);
+        } else if stick_pushed && stick.length() < STICK_RELEASE {
+            sticks_pushed.remove(&entity);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use crate::actions::GameAction;
+use crate::card_flip_sys::turn_face_up;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::game_mode::{spawn_win_message, GameMode, RankWrap};
+use crate::hit_test::card_contains;
+use crate::layout::Layout;
+use crate::sound::{PlaySound, SoundEffect};
+use crate::utils::{shuffle_deck, standard_deck};
+use crate::theme::CardImages;
+use tracing::debug;
+
+// Golf: 7 columns of 5 face-up cards. TriPeaks: three peaks of 28 cards, only the bottom row face-up.
+const GOLF_COLUMNS: usize = 7;
+const GOLF_ROWS: usize = 5;
+const TRIPEAKS_PEAKS: usize = 3;
+
+/// Marks a Golf or TriPeaks tableau card with its slot in the GolfBoard
+#[derive(Component, Clone, Copy, Debug)]
+pub struct GolfSlot(pub usize);
+
+/// The Golf/TriPeaks tableau as a pile graph: the card left in each slot, and which slots lie on top of it.
+/// A card can be played once every slot covering it is empty.
+#[derive(Resource, Default)]
+pub struct GolfBoard {
+    pub cards: Vec<Option<Entity>>,
+    pub covered_by: Vec<Vec<usize>>,
+    pub streak: u32, // TriPeaks: cards played since the last draw from the stock
+}
+
+impl GolfBoard {
+    pub fn is_exposed(&self, slot: usize) -> bool {
+        self.covered_by[slot].iter().all(|covering| self.cards[*covering].is_none())
+    }
+
+    pub fn cards_left(&self) -> usize {
+        self.cards.iter().filter(|card| card.is_some()).count()
+    }
+}
+
+/// Whether two ranks are one apart, counting King and Ace as neighbours when wrapping is on
+pub fn ranks_adjacent(a: u8, b: u8, wrap: bool) -> bool {
+    a.abs_diff(b) == 1 || (wrap && a.abs_diff(b) == 12)
+}
+
+fn shuffled_deck(deal_number: u32) -> Vec<CardId> {
+    let mut deck = standard_deck(0);
+    shuffle_deck(&mut deck, deal_number as u64);
+    deck
+}
+
+/// Spawns the Golf board: 35 cards in 7 face-up columns, one card on the waste and 16 in the stock
+pub fn setup_golf_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    let mut cards = shuffled_deck(deal_number).into_iter();
+    let mut board = GolfBoard::default();
+    for column in 0..GOLF_COLUMNS {
+        for row in 0..GOLF_ROWS {
+            let Some(card) = cards.next() else { break };
+            let slot = board.cards.len();
+            let position = Layout::tableau_position(column, GOLF_COLUMNS, row) + Vec3::Z;
+
+            let entity = create_card_entity(commands, card_images, position, card, true, (GolfSlot(slot), CardFront, OriginalPosition(position)));
+            board.cards.push(Some(entity));
+
+            // Each card is covered by the one dealt below it in the column
+            board.covered_by.push(if row + 1 < GOLF_ROWS { vec![slot + 1] } else { Vec::new() });
+        }
+    }
+
+    setup_stock_and_waste(commands, card_images, cards.collect());
+    commands.insert_resource(GameScore(board.cards_left() as u32)); // Golf scores the cards left, lower is better
+    commands.insert_resource(board);
+}
+
+/// Spawns the TriPeaks board: three overlapping peaks (rows of 3, 6, 9 and 10 cards), one card on the waste
+/// and 23 in the stock. Only the bottom row starts face-up; the rest turn over as they are uncovered.
+pub fn setup_tripeaks_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    let base_y = Layout::TABLEAU_Y - 210.0; // The bottom row
+    let mut cards = shuffled_deck(deal_number).into_iter();
+    let mut board = GolfBoard::default();
+
+    // Slot layout: row 0 holds the 3 peaks, row 1 has 2 cards per peak, row 2 has 3 per peak, row 3 is 10 cards across.
+    // (x, row, covered_by) for each slot; each row is one card wider, so the rows interleave in half-column steps.
+    // The peaks sit over the first, middle and last of 7 columns.
+    let mut slots: Vec<(f32, usize, Vec<usize>)> = Vec::new();
+    for peak in 0..TRIPEAKS_PEAKS {
+        slots.push((Layout::column_x(peak * 3, 7), 0, vec![3 + peak * 2, 3 + peak * 2 + 1]));
+    }
+    for peak in 0..TRIPEAKS_PEAKS {
+        for k in 0..2 {
+            let index = peak * 3 + k; // Row 2 card to the lower left
+            slots.push((Layout::column_x(index, 8), 1, vec![9 + index, 9 + index + 1]));
+        }
+    }
+    for j in 0..9 {
+        slots.push((Layout::column_x(j, 9), 2, vec![18 + j, 18 + j + 1]));
+    }
+    for j in 0..10 {
+        slots.push((Layout::column_x(j, 10), 3, Vec::new()));
+    }
+
+    for (x_pos, row, covered_by) in slots {
+        let Some(card) = cards.next() else { break };
+        let slot = board.cards.len();
+        let position = Vec3::new(x_pos, base_y + ((3 - row) as f32 * 50.0), (row + 1) as f32);
+        let is_face_up = row == 3;
+
+        let entity = if is_face_up {
+            create_card_entity(commands, card_images, position, card, true, (GolfSlot(slot), CardFront, OriginalPosition(position)))
+        } else {
+            create_card_entity(commands, card_images, position, card, false, (GolfSlot(slot), CardBack, OriginalPosition(position)))
+        };
+        board.cards.push(Some(entity));
+        board.covered_by.push(covered_by);
+    }
+
+    setup_stock_and_waste(commands, card_images, cards.collect());
+    commands.insert_resource(board);
+}
+
+/// The stock and waste sit where Klondike's do, so `stock_click_system` deals for these modes too
+fn setup_stock_and_waste(commands: &mut Commands, card_images: &CardImages, mut stock: Vec<CardId>) {
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.3, 0.3, 0.3),
+            custom_size: Some(CARD_SIZE),
+            ..default()
+        },
+        Transform::from_translation(WastePosition::default().0),
+        WastePile,
+    ));
+
+    // The game starts with one card turned over onto the waste
+    if let Some(card) = stock.pop() {
+        create_card_entity(commands, card_images, WastePosition::default().0 + Vec3::Z, card, true, (WastePile, CardFront));
+    }
+
+    create_card_entity(
+        commands,
+        card_images,
+        StockPosition::default().0,
+        CardId::new(0, CardSuit::Spades, 1), // Dummy card - not important for stock pile
+        false,
+        (
+            StockPile,
+            CardBack,
+        ),
+    );
+
+    commands.insert_resource(StockCards(stock));
+    commands.insert_resource(TableauPositions(Vec::new()));
+    commands.insert_resource(FoundationPositions(Vec::new()));
+    commands.insert_resource(FoundationPiles(Vec::new()));
+}
+
+/// The cards dealt into the Golf or TriPeaks layout, with the slot each one was dealt to
+pub type GolfSlotCards<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static CardData, &'static GolfSlot), With<Card>>;
+
+/// The cards played onto the waste, the top one being the card to play on
+pub type GolfWasteCards<'w, 's> = Query<'w, 's, (&'static Transform, &'static CardData), (With<WastePile>, With<Card>)>;
+
+/// The Golf/TriPeaks board and everything playing a card onto the waste touches
+#[derive(SystemParam)]
+pub struct GolfPlay<'w, 's> {
+    pub sounds: MessageWriter<'w, PlaySound>,
+    pub moves: MessageWriter<'w, MoveMade>,
+    pub card_images: CardImages<'w>,
+    pub game_mode: Res<'w, GameMode>,
+    pub rank_wrap: Res<'w, RankWrap>,
+    pub golf_board: ResMut<'w, GolfBoard>,
+    pub game_score: ResMut<'w, GameScore>,
+    pub tableau_cards: GolfSlotCards<'w, 's>,
+    pub waste_cards: GolfWasteCards<'w, 's>,
+}
+
+impl GolfPlay<'_, '_> {
+    /// The face-up, uncovered card in `slot`, if there is one to play
+    pub fn playable_card(&self, slot: usize) -> Option<Entity> {
+        let entity = self.golf_board.cards.get(slot).copied().flatten()?;
+        let (_entity, _transform, card_data, _slot) = self.tableau_cards.get(entity).ok()?;
+        (card_data.is_face_up && self.golf_board.is_exposed(slot)).then_some(entity)
+    }
+}
+
+/// Golf and TriPeaks input: a single click plays an uncovered card onto the waste
+/// when it is one rank above or below the waste card
+pub fn golf_play_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut play: GolfPlay,
+) {
+    let Some(cursor_world_pos) = game_actions.read().filter_map(GameAction::press_position).last() else { return };
+
+    // The clicked card is the highest uncovered one under the cursor
+    let Some((entity, ..)) = play.tableau_cards
+        .iter()
+        .filter(|(_entity, transform, card_data, slot)| {
+            card_data.is_face_up
+                && play.golf_board.is_exposed(slot.0)
+                && card_contains(transform.translation, cursor_world_pos)
+        })
+        .max_by(|a, b| a.1.translation.z.partial_cmp(&b.1.translation.z).unwrap())
+    else {
+        return;
+    };
+
+    play_to_waste(&mut commands, &mut play, entity);
+}
+
+/// Plays an uncovered tableau card onto the waste if it is one rank above or below the waste card,
+/// and turns over any card it uncovers. Shared by the mouse and the pile cursor.
+pub fn play_to_waste(commands: &mut Commands, play: &mut GolfPlay, entity: Entity) -> bool {
+    let GolfPlay { sounds, moves, card_images, game_mode, rank_wrap, golf_board, game_score, tableau_cards, waste_cards } = play;
+    let Ok((_entity, _transform, card_data, slot)) = tableau_cards.get(entity) else { return false };
+
+    let Some((waste_transform, waste_card)) = waste_cards
+        .iter()
+        .max_by(|a, b| a.0.translation.z.partial_cmp(&b.0.translation.z).unwrap())
+    else {
+        return false;
+    };
+
+    if !ranks_adjacent(card_data.value, waste_card.value, rank_wrap.0) {
+        debug!("{:?} {} can't go on waste card {:?} {}", card_data.suit, card_data.value, waste_card.suit, waste_card.value);
+        sounds.write(PlaySound(SoundEffect::Invalid));
+        return false;
+    }
+
+    // Play the card on top of the waste
+    let waste_position = waste_transform.translation;
+    let new_position = Vec3::new(waste_position.x, waste_position.y, waste_position.z + 1.0);
+    commands.entity(entity)
+        .insert(Transform::from_translation(new_position))
+        .insert(OriginalPosition(new_position))
+        .insert(WastePile)
+        .remove::<GolfSlot>();
+    golf_board.cards[slot.0] = None;
+    golf_board.streak += 1;
+    sounds.write(PlaySound(SoundEffect::Drop));
+    moves.write(MoveMade);
+
+    match **game_mode {
+        // Each card in a streak is worth one more than the last
+        GameMode::TriPeaks => game_score.0 += golf_board.streak,
+        _ => game_score.0 = golf_board.cards_left() as u32,
+    }
+    debug!("Played {:?} {} to the waste, streak {}, score {}", card_data.suit, card_data.value, golf_board.streak, game_score.0);
+
+    // Turn over any face-down cards this uncovered
+    for (other_entity, _transform, other_card, other_slot) in tableau_cards.iter() {
+        if other_entity == entity || other_card.is_face_up || !golf_board.is_exposed(other_slot.0) {
+            continue;
+        }
+
+        turn_face_up(commands, card_images, other_entity, other_card);
+    }
+
+    if golf_board.cards_left() == 0 {
+        debug!("Tableau cleared - game won");
+        spawn_win_message(commands);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One TriPeaks peak: the top card, the two that overlap it, and the three that overlap those
+    fn peak() -> GolfBoard {
+        GolfBoard {
+            cards: (1..=6).map(|raw| Some(Entity::from_raw_u32(raw).unwrap())).collect(),
+            covered_by: vec![vec![1, 2], vec![3, 4], vec![4, 5], Vec::new(), Vec::new(), Vec::new()],
+            streak: 0,
+        }
+    }
+
+    #[test]
+    fn uncovered_cards_are_exposed() {
+        let board = peak();
+        assert!(!board.is_exposed(0));
+        assert!(!board.is_exposed(1));
+        assert!(board.is_exposed(3));
+        assert!(board.is_exposed(5));
+    }
+
+    #[test]
+    fn a_card_is_exposed_once_every_card_covering_it_is_played() {
+        let mut board = peak();
+        board.cards[4] = None;
+        assert!(!board.is_exposed(1)); // Still under slot 3
+        board.cards[3] = None;
+        assert!(board.is_exposed(1));
+        assert!(!board.is_exposed(0)); // Slot 2 still lies on the top card
+        board.cards[5] = None;
+        board.cards[2] = None;
+        assert!(!board.is_exposed(0));
+        board.cards[1] = None;
+        assert!(board.is_exposed(0));
+        assert_eq!(board.cards_left(), 1);
+    }
+
+    #[test]
+    fn ranks_one_apart_are_adjacent_either_way() {
+        assert!(ranks_adjacent(5, 6, false));
+        assert!(ranks_adjacent(6, 5, false));
+        assert!(ranks_adjacent(12, 13, false));
+        assert!(!ranks_adjacent(5, 5, false));
+        assert!(!ranks_adjacent(5, 7, false));
+    }
+
+    #[test]
+    fn king_and_ace_are_adjacent_only_with_wrapping() {
+        assert!(!ranks_adjacent(13, 1, false));
+        assert!(!ranks_adjacent(1, 13, false));
+        assert!(ranks_adjacent(13, 1, true));
+        assert!(ranks_adjacent(1, 13, true));
+        assert!(!ranks_adjacent(13, 2, true));
+        assert!(ranks_adjacent(4, 3, true));
+    }
+}
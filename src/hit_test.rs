@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use crate::components::*;
+use crate::card_validation::is_same_pile;
+use crate::game_mode::GameMode;
+
+/// A card found under the pointer: the pile it is in and how far up that pile it sits (0 is the bottom card)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CardHit {
+    pub entity: Entity,
+    pub pile: PileId,
+    pub index: usize,
+}
+
+/// Every pile on the board for the current game mode, with the position of its base
+pub fn board_piles(game_mode: GameMode, pile_positions: &PilePositions) -> Vec<(PileId, Vec3)> {
+    let mut piles: Vec<(PileId, Vec3)> = Vec::new();
+    piles.extend(pile_positions.foundations.0.iter().enumerate().map(|(i, position)| (PileId::Foundation(i), *position)));
+    piles.extend(pile_positions.free_cells.0.iter().enumerate().map(|(i, position)| (PileId::FreeCell(i), *position)));
+    if game_mode.has_waste_pile() {
+        piles.push((PileId::Waste, pile_positions.waste.0));
+    }
+    if game_mode.has_waste_pile() || matches!(game_mode, GameMode::Spider(_)) {
+        piles.push((PileId::Stock, pile_positions.stock.0));
+    }
+    piles.extend(pile_positions.tableau.0.iter().enumerate().map(|(i, position)| (PileId::Tableau(i), *position)));
+    if let Some(position) = pile_positions.reserve.0 {
+        piles.push((PileId::Reserve, position));
+    }
+    piles
+}
+
+/// Whether `point` lands on a card lying at `position`
+pub fn card_contains(position: Vec3, point: Vec2) -> bool {
+    Rect::from_center_size(position.truncate(), CARD_SIZE).contains(point)
+}
+
+/// The pile a card lying at `position` belongs to
+pub fn pile_of(position: Vec3, piles: &[(PileId, Vec3)]) -> Option<PileId> {
+    piles
+        .iter()
+        .filter(|(_pile, base)| is_same_pile(*base, position))
+        .min_by(|a, b| (a.1.x - position.x).abs().partial_cmp(&(b.1.x - position.x).abs()).unwrap())
+        .map(|(pile, _base)| *pile)
+}
+
+/// The pile whose area contains `point`: a card-sized rectangle at its base, stretched down over
+/// any cards fanned out below it
+pub fn pile_under(point: Vec2, piles: &[(PileId, Vec3)], cards: &[(Entity, Vec3)]) -> Option<PileId> {
+    piles
+        .iter()
+        .find(|(pile, base)| {
+            let lowest_y = cards
+                .iter()
+                .filter(|(_entity, position)| pile_of(*position, piles) == Some(*pile))
+                .fold(base.y, |lowest, (_entity, position)| lowest.min(position.y));
+            let half = CARD_SIZE / 2.0;
+            Rect::from_corners(
+                Vec2::new(base.x - half.x, base.y + half.y),
+                Vec2::new(base.x + half.x, lowest_y - half.y),
+            )
+            .contains(point)
+        })
+        .map(|(pile, _base)| *pile)
+}
+
+/// The card under `point` among `cards`, with its pile and its index among the pile's cards in `cards`.
+/// Cards overlap in a fanned column, so each one only shows the strip the cards above it leave uncovered;
+/// the highest card under the point is the one whose visible part was hit.
+pub fn card_under(point: Vec2, piles: &[(PileId, Vec3)], cards: &[(Entity, Vec3)]) -> Option<CardHit> {
+    let (entity, position) = cards
+        .iter()
+        .filter(|(_entity, position)| card_contains(*position, point))
+        .max_by(|a, b| a.1.z.partial_cmp(&b.1.z).unwrap())?;
+    let pile = pile_of(*position, piles)?;
+    let index = cards
+        .iter()
+        .filter(|(_other_entity, other_position)| other_position.z < position.z && pile_of(*other_position, piles) == Some(pile))
+        .count();
+
+    Some(CardHit { entity: *entity, pile, index })
+}
@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use crate::actions::GameAction;
+
+/// Keyboard bindings: arrow keys move the pile cursor, Space/Enter picks up and drops,
+/// Escape puts the card down again, D draws, F sends to a foundation and Tab cycles suggested targets.
+/// Ctrl+Z (or Ctrl+U) undoes and Ctrl+Y redoes.
+pub fn keyboard_input_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut game_actions: MessageWriter<GameAction>,
+) {
+    let bindings = [
+        (KeyCode::ArrowLeft, GameAction::Left),
+        (KeyCode::ArrowRight, GameAction::Right),
+        (KeyCode::ArrowUp, GameAction::Up),
+        (KeyCode::ArrowDown, GameAction::Down),
+        (KeyCode::Space, GameAction::Select),
+        (KeyCode::Enter, GameAction::Select),
+        (KeyCode::Escape, GameAction::Cancel),
+        (KeyCode::KeyF, GameAction::ToFoundation),
+        (KeyCode::KeyD, GameAction::Draw),
+        (KeyCode::Tab, GameAction::NextTarget),
+    ];
+
+    for (key, action) in bindings {
+        if keyboard_input.just_pressed(key) {
+            game_actions.write(action);
+        }
+    }
+
+    let control = keyboard_input.pressed(KeyCode::ControlLeft) || keyboard_input.pressed(KeyCode::ControlRight);
+    if control && (keyboard_input.just_pressed(KeyCode::KeyZ) || keyboard_input.just_pressed(KeyCode::KeyU)) {
+        game_actions.write(GameAction::Undo);
+    } else if control && keyboard_input.just_pressed(KeyCode::KeyY) {
+        game_actions.write(GameAction::Redo);
+    }
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use crate::components::*;
+use tracing::debug;
+
+/// Where everything goes. The board is laid out in board units, designed for a 1280×720 window
+/// (a card is 80×120), and the camera zooms so that design fills the real window. Extra room from
+/// a window of a different shape goes to the sides, or below the tableau for a tall window, and the
+/// score, mode label and undo button follow the window's edges.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub zoom: f32, // Window pixels per board unit
+    pub visible: Rect, // The part of the board the window shows
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::from_window(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT))
+    }
+}
+
+impl Layout {
+    pub const COLUMN_SPACING: f32 = 100.0; // Between neighbouring columns, and between piles in the top row
+    pub const FAN_OFFSET: f32 = 30.0; // How far each card in a column sits below the one it covers
+    pub const FACE_DOWN_FAN_OFFSET: f32 = 12.0; // Face-down cards only need to show their edge
+    pub const MIN_FAN_SQUEEZE: f32 = 0.3; // A column is never squeezed below this share of its full spacing
+    pub const BOTTOM_MARGIN: f32 = 10.0; // Gap kept between the lowest card and the bottom of the window
+    pub const TOP_ROW_Y: f32 = WINDOW_HEIGHT / 2.0 - 100.0; // Stock, waste, foundations and free cells
+    pub const TABLEAU_Y: f32 = WINDOW_HEIGHT / 2.0 - 250.0; // The first card of each column
+    pub const TOP_ROW_MIN_Y: f32 = WINDOW_HEIGHT / 2.0 - 175.0; // Anything above this line is in the top row
+    pub const KLONDIKE_COLUMNS: usize = 7; // Also the grid Canfield, Golf and the stock and waste of most modes use
+
+    /// Fits the board to a window of the given size
+    pub fn from_window(size: Vec2) -> Self {
+        let zoom = (size.x / WINDOW_WIDTH).min(size.y / WINDOW_HEIGHT).max(0.01);
+        let half_size = size / zoom / 2.0;
+        let top = WINDOW_HEIGHT / 2.0; // The top of the board stays at the top of the window
+        Layout {
+            zoom,
+            visible: Rect::new(-half_size.x, top - 2.0 * half_size.y, half_size.x, top),
+        }
+    }
+
+    /// x of `column` when `columns` columns are spread evenly about the middle of the board
+    pub fn column_x(column: usize, columns: usize) -> f32 {
+        (column as f32 - (columns as f32 - 1.0) / 2.0) * Layout::COLUMN_SPACING
+    }
+
+    /// Where card `row` of a tableau column sits (row 0 is the bottom card)
+    pub fn tableau_position(column: usize, columns: usize, row: usize) -> Vec3 {
+        Vec3::new(Layout::column_x(column, columns), Layout::TABLEAU_Y - row as f32 * Layout::FAN_OFFSET, row as f32)
+    }
+
+    /// Where a pile in the top row sits, on the same grid as the columns
+    pub fn top_row_position(column: usize, columns: usize) -> Vec3 {
+        Vec3::new(Layout::column_x(column, columns), Layout::TOP_ROW_Y, 0.0)
+    }
+
+    /// Lowest the middle of a card may sit and still be fully in the window
+    pub fn playfield_bottom(&self) -> f32 {
+        self.visible.min.y + CARD_SIZE.y / 2.0 + Layout::BOTTOM_MARGIN
+    }
+
+    /// The y of each card in a column whose bottom card sits at `base_y`, given which cards are face-down
+    /// (bottom card first). Cards fan out by the full offsets while they fit above the bottom of the window;
+    /// a longer column is squeezed evenly to fit, and spreads out again as cards are taken off it.
+    pub fn column_ys(&self, base_y: f32, face_down: &[bool]) -> Vec<f32> {
+        let gaps: Vec<f32> = face_down
+            .iter()
+            .take(face_down.len().saturating_sub(1)) // Nothing sits below the top card
+            .map(|face_down| if *face_down { Layout::FACE_DOWN_FAN_OFFSET } else { Layout::FAN_OFFSET })
+            .collect();
+        let needed: f32 = gaps.iter().sum();
+        let room = (base_y - self.playfield_bottom()).max(0.0);
+        let squeeze = if needed > room { (room / needed).max(Layout::MIN_FAN_SQUEEZE) } else { 1.0 };
+
+        let mut y = base_y;
+        let mut ys = vec![y];
+        for gap in gaps {
+            y -= gap * squeeze;
+            ys.push(y);
+        }
+        ys
+    }
+
+    /// Where something pinned to the window sits: `anchor` picks the point of the visible board
+    /// (-1 to 1 across and up, so (-1, 1) is the top left corner) and `offset` moves it from there
+    pub fn anchored(&self, anchor: Vec2, offset: Vec2) -> Vec2 {
+        self.visible.center() + anchor * self.visible.half_size() + offset
+    }
+}
+
+/// Keeps an entity at a fixed place relative to the window's edges, see `Layout::anchored`
+#[derive(Component, Clone, Copy)]
+pub struct ScreenAnchor {
+    pub anchor: Vec2,
+    pub offset: Vec2,
+}
+
+/// Refits the board to the window: works out the layout from the window size, zooms and moves the
+/// camera to match and puts the screen-anchored entities back at their edges
+pub fn layout_system(
+    window_query: Query<&Window>,
+    mut layout: ResMut<Layout>,
+    mut camera_query: Query<(&mut Transform, &mut Projection), With<Camera2d>>,
+    mut anchored_query: Query<(&mut Transform, Ref<ScreenAnchor>), Without<Camera2d>>,
+) {
+    let Ok(window) = window_query.single() else { return };
+    if window.width() <= 0.0 || window.height() <= 0.0 {
+        return; // Minimised
+    }
+    layout.set_if_neq(Layout::from_window(window.size()));
+
+    if layout.is_changed() {
+        debug!("Window is {}x{}, laying the board out at zoom {}", window.width(), window.height(), layout.zoom);
+        for (mut camera_transform, mut projection) in camera_query.iter_mut() {
+            if let Projection::Orthographic(orthographic) = &mut *projection {
+                orthographic.scale = 1.0 / layout.zoom;
+            }
+            let center = layout.visible.center();
+            camera_transform.translation.x = center.x;
+            camera_transform.translation.y = center.y;
+        }
+    }
+
+    for (mut transform, anchor) in anchored_query.iter_mut() {
+        if layout.is_changed() || anchor.is_added() {
+            let position = layout.anchored(anchor.anchor, anchor.offset);
+            transform.translation.x = position.x;
+            transform.translation.y = position.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_short_column_fans_out_fully() {
+        let layout = Layout::default();
+        let ys = layout.column_ys(100.0, &[true, true, false, false]);
+        let full = [100.0, 100.0 - Layout::FACE_DOWN_FAN_OFFSET, 100.0 - 2.0 * Layout::FACE_DOWN_FAN_OFFSET, 100.0 - 2.0 * Layout::FACE_DOWN_FAN_OFFSET - Layout::FAN_OFFSET];
+        assert_eq!(ys, full);
+    }
+
+    #[test]
+    fn the_top_card_adds_no_gap() {
+        let layout = Layout::default();
+        assert_eq!(layout.column_ys(100.0, &[]), vec![100.0]);
+        assert_eq!(layout.column_ys(100.0, &[false]), vec![100.0]);
+        assert_eq!(layout.column_ys(100.0, &[false, true]), vec![100.0, 100.0 - Layout::FAN_OFFSET]);
+    }
+
+    #[test]
+    fn a_long_column_is_squeezed_to_end_at_the_bottom_of_the_window() {
+        let layout = Layout::default();
+        let base_y = Layout::TABLEAU_Y;
+        let ys = layout.column_ys(base_y, &[false; 20]); // 19 full gaps would run off the window
+        assert!(19.0 * Layout::FAN_OFFSET > base_y - layout.playfield_bottom());
+        assert_eq!(ys.len(), 20);
+        assert!((ys[19] - layout.playfield_bottom()).abs() < 0.01);
+        let gaps: Vec<f32> = ys.windows(2).map(|pair| pair[0] - pair[1]).collect();
+        assert!(gaps.iter().all(|gap| (gap - gaps[0]).abs() < 0.01)); // Evenly
+    }
+
+    #[test]
+    fn squeezing_stops_at_the_minimum() {
+        let layout = Layout::default();
+        let base_y = layout.playfield_bottom() + 10.0; // Next to no room at all
+        let ys = layout.column_ys(base_y, &[false; 5]);
+        assert!((ys[0] - ys[1] - Layout::FAN_OFFSET * Layout::MIN_FAN_SQUEEZE).abs() < 0.01);
+    }
+
+    #[test]
+    fn a_taller_window_leaves_more_room_before_squeezing() {
+        let short = Layout::from_window(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT));
+        let tall = Layout::from_window(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT * 1.5));
+        let face_down = [false; 18];
+        let short_ys = short.column_ys(Layout::TABLEAU_Y, &face_down);
+        let tall_ys = tall.column_ys(Layout::TABLEAU_Y, &face_down);
+        assert!(tall_ys[17] < short_ys[17]);
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+use bevy::asset::{AssetLoadError, LoadState, RenderAssetUsages};
+use bevy::asset::io::AssetReaderError;
+use bevy::ecs::system::SystemParam;
+use bevy::image::ImageLoaderSettings;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::mouse::MouseButton;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use bevy::tasks::futures::check_ready;
+use crate::card_atlas::{face_index, install_card_atlas, pack_card_atlas, shrink_card_image, CardAtlas, CardTile, ATLAS_CARDS, BACK_INDEX};
+use crate::card_backs::CardBacks;
+use crate::components::*;
+use crate::layout::ScreenAnchor;
+use crate::theme::DeckThemes;
+use tracing::{debug, warn};
+
+const MAX_REPORTED_FILES: usize = 12; // The rest are only logged
+
+/// The deck's images while they load, one per atlas slot. Each is shrunk to its slot size as soon as it
+/// is in and the full-size image let go, so the whole deck is never held at full size. Once every slot
+/// is ready the tiles are packed into the card atlas.
+#[derive(Resource)]
+pub struct CardArt {
+    slots: Vec<SlotArt>,
+    reported: bool, // Some images failed and the player has been told which
+}
+
+/// How far one atlas slot's image has got
+enum SlotArt {
+    Drawn, // A face the theme draws, with nothing to load
+    Loading(Handle<Image>),
+    Shrinking(Task<Option<CardTile>>), // On the async compute pool
+    Ready(Option<CardTile>), // None if the image couldn't be used; the slot is left plain
+    Failed(String, &'static str), // The image's path and what went wrong
+}
+
+/// The loading progress, and then the report of any images that couldn't be loaded
+#[derive(Component)]
+pub struct LoadingText;
+
+/// Starts loading every face of the chosen theme and the chosen back, and puts up the loading screen.
+/// Runs at startup and again whenever the deck theme or card back is switched.
+pub fn start_loading_card_art(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    deck_themes: Res<DeckThemes>,
+    card_backs: Res<CardBacks>,
+) {
+    let theme = deck_themes.current();
+    let load = |path: String| -> Handle<Image> {
+        // Only needed on the CPU, to be packed into the atlas
+        asset_server.load_with_settings(path, |settings: &mut ImageLoaderSettings| settings.asset_usage = RenderAssetUsages::MAIN_WORLD)
+    };
+
+    let mut slots: Vec<SlotArt> = (0..ATLAS_CARDS).map(|_index| SlotArt::Drawn).collect();
+    for suit in [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Clubs, CardSuit::Spades] {
+        for value in 1..=13 {
+            if let Some(path) = theme.front_image(suit, value) {
+                slots[face_index(suit, value)] = SlotArt::Loading(load(path)); // Drawn faces have nothing to load
+            }
+        }
+    }
+    let back = card_backs.current_image().map_or_else(|| theme.back_image(), str::to_string);
+    slots[BACK_INDEX] = SlotArt::Loading(load(back));
+
+    let total = slots.iter().filter(|slot| !matches!(slot, SlotArt::Drawn)).count();
+    debug!("Loading {} card images for the {} deck", total, theme.name);
+    commands.spawn((
+        Text2d::new(format!("Loading cards... 0 / {}", total)),
+        Transform::from_xyz(0.0, 0.0, 60.0),
+        ScreenAnchor { anchor: Vec2::ZERO, offset: Vec2::ZERO }, // Middle of the window
+        LoadingText,
+    ));
+    commands.insert_resource(CardArt { slots, reported: false });
+}
+
+/// Where the deck's images are loaded, taken from once shrunk, and packed into the card atlas
+#[derive(SystemParam)]
+pub struct AtlasAssets<'w> {
+    asset_server: Res<'w, AssetServer>,
+    images: ResMut<'w, Assets<Image>>,
+    layouts: ResMut<'w, Assets<TextureAtlasLayout>>,
+    card_atlas: Option<ResMut<'w, CardAtlas>>,
+}
+
+/// Any key, mouse button or finger going down, to carry on past the report of failed images
+#[derive(SystemParam)]
+pub struct AnyPress<'w> {
+    keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    mouse_input: Res<'w, ButtonInput<MouseButton>>,
+    touches: Res<'w, Touches>,
+}
+
+impl AnyPress<'_> {
+    /// Whether anything was pressed this frame
+    fn just_pressed(&self) -> bool {
+        self.keyboard_input.get_just_pressed().next().is_some()
+            || self.mouse_input.get_just_pressed().next().is_some()
+            || self.touches.any_just_pressed()
+    }
+}
+
+/// Shows how many of the card images are in. Each one is handed to the async compute pool to be shrunk
+/// as soon as it has loaded. Once they all have, they are packed into the card atlas and play starts; if
+/// some failed they are listed first, and play starts when the player presses a key or clicks. Those
+/// cards are drawn plainly (see the fallback face system).
+pub fn loading_progress_system(
+    mut commands: Commands,
+    assets: AtlasAssets,
+    mut card_art: ResMut<CardArt>,
+    deck_themes: Res<DeckThemes>,
+    any_press: AnyPress,
+    mut loading_text_query: Query<(Entity, &mut Text2d), With<LoadingText>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Ok((text_entity, mut text)) = loading_text_query.single_mut() else { return };
+    let AtlasAssets { asset_server, mut images, mut layouts, mut card_atlas } = assets;
+
+    if !card_art.reported {
+        let card_size = deck_themes.current().card_size;
+        for (index, slot) in card_art.slots.iter_mut().enumerate() {
+            match slot {
+                SlotArt::Loading(handle) => match asset_server.load_state(&*handle) {
+                    LoadState::Loaded => {
+                        // Take the image out of the asset store, so it is freed once it has been shrunk
+                        let Some(image) = images.remove(&*handle) else { continue };
+
+                        // Faces drawn at a different size than the theme says are still used, just resized
+                        if index != BACK_INDEX && image.size() != card_size {
+                            warn!("Card image for atlas slot {} is {}x{}, not the theme's {}x{}", index, image.size().x, image.size().y, card_size.x, card_size.y);
+                        }
+                        *slot = SlotArt::Shrinking(AsyncComputeTaskPool::get().spawn(async move { shrink_card_image(image, index) }));
+                    }
+                    LoadState::Failed(error) => *slot = SlotArt::Failed(image_path(handle), describe_failure(&error)),
+                    LoadState::NotLoaded | LoadState::Loading => {}
+                },
+                SlotArt::Shrinking(task) => {
+                    if let Some(tile) = check_ready(task) {
+                        *slot = SlotArt::Ready(tile);
+                    }
+                }
+                SlotArt::Drawn | SlotArt::Ready(_) | SlotArt::Failed(..) => {}
+            }
+        }
+
+        let total = card_art.slots.iter().filter(|slot| !matches!(slot, SlotArt::Drawn)).count();
+        let ready = card_art.slots.iter().filter(|slot| matches!(slot, SlotArt::Ready(_))).count();
+        let failures: Vec<(&str, &str)> = card_art.slots
+            .iter()
+            .filter_map(|slot| match slot {
+                SlotArt::Failed(path, problem) => Some((path.as_str(), *problem)),
+                _ => None,
+            })
+            .collect();
+        if ready + failures.len() < total {
+            let progress = format!("Loading cards... {} / {}", ready, total);
+            if text.0 != progress {
+                text.0 = progress;
+            }
+            return;
+        }
+
+        if !failures.is_empty() {
+            // List what went wrong, then wait for the player to carry on
+            for (path, problem) in &failures {
+                warn!("Card image {} {}", path, problem);
+            }
+            let mut report = format!("{} of {} card images couldn't be loaded:\n", failures.len(), total);
+            for (path, problem) in failures.iter().take(MAX_REPORTED_FILES) {
+                report += &format!("{} {}\n", path, problem);
+            }
+            if failures.len() > MAX_REPORTED_FILES {
+                report += &format!("...and {} more (see the log)\n", failures.len() - MAX_REPORTED_FILES);
+            }
+            report += "\nThose cards will be drawn plainly. Press any key or click to play.";
+            text.0 = report;
+            card_art.reported = true;
+            return;
+        }
+        debug!("All {} card images loaded and shrunk", total);
+    } else if !any_press.just_pressed() {
+        return; // Still showing the report
+    }
+
+    let tiles: Vec<Option<CardTile>> = card_art.slots
+        .iter_mut()
+        .map(|slot| match std::mem::replace(slot, SlotArt::Drawn) {
+            SlotArt::Ready(tile) => tile,
+            _ => None,
+        })
+        .collect();
+    let packed = pack_card_atlas(&tiles);
+    install_card_atlas(&mut commands, &mut images, &mut layouts, card_atlas.as_deref_mut(), packed);
+
+    commands.remove_resource::<CardArt>(); // The atlas has them now
+    commands.entity(text_entity).despawn();
+    next_state.set(GameState::Playing);
+}
+
+fn image_path(image: &Handle<Image>) -> String {
+    image.path().map_or_else(|| format!("{:?}", image.id()), |path| path.to_string())
+}
+
+/// Why an image failed, in words the player can act on
+fn describe_failure(error: &AssetLoadError) -> &'static str {
+    match error {
+        AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_)) => "is missing",
+        AssetLoadError::AssetReaderError(_) => "couldn't be read",
+        AssetLoadError::AssetLoaderError(_) => "isn't a readable image (corrupt or the wrong format)",
+        AssetLoadError::MissingAssetLoaderForExtension(_) => "isn't a supported image type",
+        _ => "failed to load",
+    }
+}
@@ -10,23 +10,72 @@ mod card_flip_sys;
 mod card_entity;
 mod foundation;
 mod stock_click;
-mod waste_click;
 mod undo;
 mod visual_stacking;
+mod game_mode;
+mod freecell;
+mod spider;
+mod ferris;
+mod pyramid;
+mod golf;
+mod yukon;
+mod canfield;
+mod double_klondike;
+mod cursor;
+mod keyboard;
+mod gamepad;
+mod pointer;
+mod actions;
+mod hit_test;
+mod layout;
+mod tween;
+mod drop_highlight;
+mod feedback;
+mod sound;
+mod theme;
+mod card_backs;
+mod settings;
+mod fallback_faces;
+mod loading;
+mod card_atlas;
+mod options;
 
 use bevy::prelude::*;
 use components::*;
-use setup::setup_game;
+use setup::{setup_game, spawn_camera};
 use card_drag::*;
 use card_flip_sys::*;
-use card_entity::*;
 use foundation::*;
+use card_double_click::quick_move_system;
 use stock_click::*;
-use waste_click::*;
 use undo::*;
 use visual_stacking::*;
+use game_mode::*;
+use spider::*;
+use ferris::*;
+use pyramid::*;
+use golf::*;
+use cursor::*;
+use keyboard::*;
+use gamepad::*;
+use pointer::*;
+use actions::GameAction;
+use layout::*;
+use tween::*;
+use drop_highlight::drop_highlight_system;
+use feedback::*;
+use sound::*;
+use theme::*;
+use card_backs::*;
+use fallback_faces::*;
+use loading::*;
+use options::*;
+use bevy::transform::TransformSystems;
+use bevy::input::InputSystems;
 
 fn main() {
+    let (game_mode, deal_number, rank_wrap) = parse_command_line();
+
     App::new()
         .insert_resource(ClearColor(Color::srgb(0.1, 0.4, 0.1))) // Green background for solitaire
         .insert_resource(GameScore(0))
@@ -34,30 +83,121 @@ fn main() {
         .insert_resource(StockCards(Vec::new()))
         .insert_resource(TableauPositions(Vec::new()))
         .insert_resource(FoundationPiles(vec![Vec::new(); 4])) // Initialize 4 empty foundation piles
-        .insert_resource(ClickedEntity(None)) // Initialize clicked entity tracking for double-click detection
+        .insert_resource(FoundationPositions(Vec::new()))
+        .insert_resource(StockPosition::default()) // Double Klondike moves the stock and waste to make room
+        .insert_resource(WastePosition::default())
+        .insert_resource(ReservePosition::default()) // Canfield only
+        .insert_resource(RankRules::default()) // Canfield changes the foundation base and wraps ranks
+        .insert_resource(FreeCells(Vec::new())) // FreeCell only
+        .insert_resource(FreeCellPositions(Vec::new()))
+        .insert_resource(PyramidBoard::default()) // Pyramid only
+        .insert_resource(PyramidDiscard::default())
+        .insert_resource(GolfBoard::default()) // Golf and TriPeaks only
+        .insert_resource(PileCursor::default()) // Keyboard and gamepad play
+        .insert_resource(OptionsPanel::default()) // O lists the deck themes and card backs to choose from
+        .insert_resource(Layout::default()) // Refitted to the real window size by the layout system
+        .add_message::<GameAction>() // Mouse, touch, keyboard and gamepad input, read by the game logic
+        .add_message::<MoveRejected>() // Drops the rules turned down, for the shake and the hint toast
+        .insert_resource(MoveHints(true)) // H turns the hint toasts off
+        .add_message::<PlaySound>() // Sound effects asked for by the game logic
+        .insert_resource(SoundSettings::load()) // --volume, --effects-volume and --mute, or as last saved
+        .insert_resource(game_mode) // Klondike unless --mode says otherwise
+        .insert_resource(deal_number)
+        .insert_resource(rank_wrap) // Golf/TriPeaks: --wrap lets Kings and Aces play on each other
         .insert_resource(UndoStack(Vec::new())) // Initialize undo stack
+        .insert_resource(RedoStack(Vec::new()))
+        .add_message::<MoveMade>() // Moves made, recorded for undo once the board has settled
         .add_plugins(DefaultPlugins)      
-        .add_systems(Startup, setup_game)
+        .init_state::<GameState>() // Loading until the deck's images are in
+        .add_systems(Startup, ((load_deck_themes, load_card_backs), spawn_camera, load_sounds).chain())
+        .add_systems(Startup, load_fallback_faces) // Suit shapes and font for cards drawn without images
+        .add_systems(Update, (
+            start_loading_card_art.run_if(not(resource_exists::<CardArt>)), // Load the deck art at startup, and again after a theme or back switch
+            loading_progress_system.run_if(resource_exists::<CardArt>), // Show progress and any images that failed, then pack the card atlas
+        ).chain().run_if(in_state(GameState::Loading)))
+        .add_systems(OnEnter(GameState::Playing), setup_game.run_if(run_once)) // Deal once the deck has first loaded
+        .add_systems(PreUpdate, (
+            layout_system, // Fit the board to the window and keep the score and undo button at its edges
+            (
+                pointer_input_system, // Mouse and touch become press, drag, drop and quick move actions
+                keyboard_input_system, // Arrow keys, Space/Enter, F, D, Tab and Ctrl+Z/Y become actions
+                gamepad_input_system, // D-pad, stick and face buttons become the same actions
+            ).run_if(in_state(GameState::Playing).and(options_closed)), // The options panel has the keys while it is open
+        ).after(InputSystems))
         .add_systems(
             Update,
             (
                 // Input systems first
-                stock_click_system, // Handle stock pile cycling (deal to waste, recycle waste to stock)
-                waste_card_click_system, // Handle waste pile card clicks
-                double_click_foundation_system, // Move cards to foundation piles on double-click
-                undo_button_system, // Handle undo button clicks
-                // Unified drag and drop system (handles both drag-and-drop and double-clicks)
-                card_drag_drop_system,
+                stock_click_system.run_if(has_waste_pile), // Handle stock pile cycling (deal to waste, recycle waste to stock)
+                spider_stock_click_system.run_if(is_spider), // Deal a card onto every Spider column
+                quick_move_system.run_if(uses_tableau_drag), // Double-click or double-tap sends a card to a foundation or column
+                skip_deal_system, // A click during the deal animation finishes it
+                new_game_system, // N deals a new game, M switches game mode
+                // Unified drag and drop system (double-clicks are handled by the quick move system)
+                card_drag_drop_system.run_if(uses_tableau_drag),
+                drop_highlight_system.run_if(uses_tableau_drag).after(card_drag_drop_system), // Outline where the dragged cards can go
+                pyramid_input_system.run_if(is_pyramid), // Pair up Pyramid cards by clicking or dragging
+                golf_play_system.run_if(is_golf_or_tripeaks), // Click a card to play it onto the Golf/TriPeaks waste
+                ferris_full_stack_click_system.run_if(is_ferris), // Click a full stack to send it to a foundation
+                cursor_play_system.run_if(uses_tableau_drag), // Move the pile cursor, pick up and drop cards
+                cursor_pyramid_system.run_if(is_pyramid), // Pair up Pyramid cards with the pile cursor
+                cursor_golf_system.run_if(is_golf_or_tripeaks), // Play Golf/TriPeaks cards to the waste with the pile cursor
+                cursor_draw_system.run_if(has_waste_pile.or(is_spider)), // Draw from the stock
+                move_rejected_system, // Shake turned-down cards and show why in a toast
+                toast_system, // Fade out the hint toast
+            ).run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                // Sound, after the game logic has asked for it
+                sound_settings_system, // S mutes, - and = set the volume, and the master volume follows the settings
+                play_sounds_system, // Play this frame's sound effects, turned-down moves and the win fanfare
+                unplayed_sounds_system, // Clear away sounds that can't play because there is no audio device
             ),
         )
+        .add_systems(
+            Update,
+            (
+                // Card art options
+                theme_switch_system.run_if(options_closed), // T switches to the next deck theme
+                card_back_switch_system.run_if(options_closed), // B switches to the next card back
+                options_panel_system, // O lists every deck theme and card back to pick from
+                fallback_faces_system, // Draw the faces of cards with no image, or whose image failed to load
+            ).run_if(in_state(GameState::Playing)),
+        )
         .add_systems(
             PostUpdate,
             (
                 // Update systems last
                 flip_cards_system, // Handle flipping cards underneath moved cards
                 foundation_validation_system, // Foundation validation (disabled - no auto-move)
-                undo_system, // Handle undo functionality
+                spider_complete_run_system.run_if(is_spider).before(flip_cards_system), // Move complete Spider runs to the foundations
+                win_check_system, // Show the win message once every foundation is complete
+                ferris_win_check_system.run_if(is_ferris), // Ferris is won once every card is in play
+                pyramid_win_check_system.run_if(is_pyramid), // Pyramid is won once the pyramid is cleared
+                score_display_system, // Keep the score text up to date
                 update_tableau_visual_stacking_system, // Maintain visual stacking of tableau cards. Never disable this.
+            ).before(start_card_tweens_system).run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                // Undo, once a move and the cards it turned over have settled
+                record_move_system, // Record the board as dealt and after every move
+                undo_system, // Ctrl+Z, Ctrl+Y, the gamepad shoulder buttons and the undo button step back and forth through it
+            ).chain()
+                .after(flip_cards_system)
+                .after(update_tableau_visual_stacking_system)
+                .before(start_card_tweens_system)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                // Animation, once every card is in its new place
+                start_card_tweens_system.before(TransformSystems::Propagate), // Cards that jumped slide there instead
+                animate_cards_system.after(TransformSystems::Propagate), // Draw moving and flipping cards part-way
             ),
         )
         .run();
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use bevy::text::Justify;
+use crate::card_backs::CardBacks;
+use crate::components::*;
+use crate::feedback::spawn_toast;
+use crate::layout::ScreenAnchor;
+use crate::settings::save_setting;
+use crate::theme::DeckThemes;
+use crate::tween::Animating;
+use tracing::debug;
+
+const PANEL_WIDTH: f32 = 380.0;
+const LINE_HEIGHT: f32 = 26.0;
+const EXTRA_LINES: usize = 8; // Title, headings, gaps and the key help around the list
+
+/// The options panel, opened with O. While it is open the arrow keys, Enter and Escape work the panel
+/// rather than the pile cursor.
+#[derive(Resource, Default)]
+pub struct OptionsPanel {
+    pub open: bool,
+    selected: usize, // Row of the list: the deck themes, then the card backs
+}
+
+/// The panel's background and text, despawned together when it closes
+#[derive(Component)]
+pub struct OptionsPanelPart;
+
+/// The card art to choose between, and the loading screen a new choice goes through
+#[derive(SystemParam)]
+pub struct CardArtChoices<'w> {
+    pub deck_themes: ResMut<'w, DeckThemes>,
+    pub card_backs: ResMut<'w, CardBacks>,
+    pub next_state: ResMut<'w, NextState<GameState>>,
+}
+
+/// Run condition: the options panel is closed, so the keyboard plays the game
+pub fn options_closed(options: Res<OptionsPanel>) -> bool {
+    !options.open
+}
+
+/// The panel's text: every deck theme and card back on its own line, the ones in use marked and the
+/// selected row pointed at
+pub fn options_text(themes: &[&str], current_theme: usize, backs: &[&str], current_back: usize, selected: usize) -> String {
+    let mut text = String::from("Options");
+    let mut row = 0;
+    for (heading, names, current) in [("Deck theme", themes, current_theme), ("Card back", backs, current_back)] {
+        text += &format!("\n\n{}", heading);
+        for (i, name) in names.iter().enumerate() {
+            let pointer = if row == selected { "> " } else { "  " };
+            let in_use = if i == current { "  (in use)" } else { "" };
+            text += &format!("\n{}{}{}", pointer, name, in_use);
+            row += 1;
+        }
+    }
+    text + "\n\nUp and Down to choose, Enter to use, O or Escape to close"
+}
+
+/// O opens and closes the options panel, which lists every deck theme and card back. Up and Down move
+/// through them, and Enter (or Space) switches to the selected one, remembers it for next time and
+/// closes the panel while the new art loads.
+pub fn options_panel_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut options: ResMut<OptionsPanel>,
+    choices: CardArtChoices,
+    animating_query: Query<(), (With<Card>, Animating)>,
+    mut panel_query: Query<(Entity, Option<&mut Text2d>), With<OptionsPanelPart>>,
+) {
+    let CardArtChoices { mut deck_themes, mut card_backs, mut next_state } = choices;
+    let theme_count = deck_themes.themes.len();
+    let rows = theme_count + card_backs.designs.len();
+    let text = |options: &OptionsPanel, deck_themes: &DeckThemes, card_backs: &CardBacks| {
+        let themes: Vec<&str> = deck_themes.themes.iter().map(|theme| theme.name.as_str()).collect();
+        let backs: Vec<&str> = card_backs.designs.iter().map(|design| design.name.as_str()).collect();
+        options_text(&themes, deck_themes.current, &backs, card_backs.current, options.selected)
+    };
+
+    if !options.open {
+        if keyboard_input.just_pressed(KeyCode::KeyO) {
+            debug!("Opening the options panel");
+            options.open = true;
+            options.selected = deck_themes.current;
+            spawn_options_panel(&mut commands, text(&options, &deck_themes, &card_backs), rows);
+        }
+        return;
+    }
+
+    let mut close = keyboard_input.any_just_pressed([KeyCode::KeyO, KeyCode::Escape]);
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        options.selected = (options.selected + 1) % rows;
+    } else if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        options.selected = (options.selected + rows - 1) % rows;
+    }
+
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::Space]) {
+        if !animating_query.is_empty() {
+            debug!("Not switching card art while cards are moving");
+        } else if options.selected < theme_count {
+            if deck_themes.current != options.selected {
+                deck_themes.current = options.selected;
+                let theme = deck_themes.current();
+                debug!("Switching to deck theme {:?}", theme.name);
+                save_setting("theme", &theme.name);
+                spawn_toast(&mut commands, format!("Deck: {}", theme.name));
+                next_state.set(GameState::Loading);
+            }
+            close = true;
+        } else {
+            let back = options.selected - theme_count;
+            if card_backs.current != back {
+                card_backs.current = back;
+                let design = card_backs.current();
+                debug!("Switching to card back {:?}", design.name);
+                save_setting("back", &design.name);
+                spawn_toast(&mut commands, format!("Card back: {}", design.name));
+                next_state.set(GameState::Loading);
+            }
+            close = true;
+        }
+    }
+
+    if close {
+        debug!("Closing the options panel");
+        options.open = false;
+        for (entity, _text) in panel_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    } else if options.is_changed() {
+        for (_entity, panel_text) in panel_query.iter_mut() {
+            if let Some(mut panel_text) = panel_text {
+                panel_text.0 = text(&options, &deck_themes, &card_backs);
+            }
+        }
+    }
+}
+
+/// A dark box in the middle of the window with the list on it, sized to fit `rows` choices
+fn spawn_options_panel(commands: &mut Commands, text: String, rows: usize) {
+    commands.spawn((
+        Sprite {
+            color: Color::srgba(0.0, 0.0, 0.0, 0.85),
+            custom_size: Some(Vec2::new(PANEL_WIDTH, (rows + EXTRA_LINES) as f32 * LINE_HEIGHT)),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 55.0), // Above moving cards, below the toast
+        ScreenAnchor { anchor: Vec2::ZERO, offset: Vec2::ZERO }, // Middle of the window
+        OptionsPanelPart,
+    ));
+    commands.spawn((
+        Text2d::new(text),
+        TextLayout::new_with_justify(Justify::Left),
+        Transform::from_xyz(0.0, 0.0, 56.0),
+        ScreenAnchor { anchor: Vec2::ZERO, offset: Vec2::ZERO },
+        OptionsPanelPart,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_panel_lists_themes_then_backs_with_the_selected_row_pointed_at() {
+        let text = options_text(&["Rustaceans", "Classic"], 0, &["Deck", "Evil Ferris"], 1, 3);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[2], "Deck theme");
+        assert_eq!(lines[3], "  Rustaceans  (in use)");
+        assert_eq!(lines[4], "  Classic");
+        assert_eq!(lines[6], "Card back");
+        assert_eq!(lines[7], "  Deck");
+        assert_eq!(lines[8], "> Evil Ferris  (in use)");
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy::input::ButtonInput;
+use bevy::input::mouse::MouseButton;
+use bevy::input::touch::Touches;
+use crate::actions::GameAction;
+use tracing::debug;
+
+// Gesture timing, the same for mouse and touch
+const DOUBLE_TAP_SECS: f64 = 0.5;
+const DRAG_HOLD_SECS: f64 = 0.2; // Holding this long starts a drag, leaving time for a double-click
+const DRAG_SLOP: f32 = 10.0; // Sliding this far starts a drag straight away instead of waiting for the hold
+// The two clicks of a double-click have to land on the same spot, give or take a drag's worth.
+// Fingers are much less precise than a mouse, so the two taps of a double-tap only need to land near each other.
+const DOUBLE_CLICK_SLOP: f32 = DRAG_SLOP;
+const DOUBLE_TAP_TOUCH_SLOP: f32 = 30.0;
+
+/// What the player's pointer is doing, whether it is the mouse or a finger on a touch screen.
+/// Positions are in world coordinates.
+#[derive(Default)]
+pub struct Pointer {
+    position: Option<Vec2>, // Last known position, kept when the mouse leaves the window so a drag can still be dropped
+    just_pressed: bool,
+    pressed: bool,
+    just_released: bool,
+    double_tap: bool, // This press completes a double-click or double-tap
+    drag_started: bool, // Held long enough, or slid far enough, to count as a drag
+    is_touch: bool,
+    press_start: Option<(f64, Vec2)>,
+    last_tap: Option<(f64, Vec2)>,
+    touch_id: Option<u64>, // The finger being followed; other fingers are ignored
+}
+
+impl Pointer {
+    /// Records a new press and works out whether it completes a double-tap
+    fn press(&mut self, now: f64, position: Vec2) {
+        self.just_pressed = true;
+        self.pressed = true;
+        let slop = if self.is_touch { DOUBLE_TAP_TOUCH_SLOP } else { DOUBLE_CLICK_SLOP };
+        self.double_tap = self.last_tap.is_some_and(|(time, last_position)| {
+            now - time < DOUBLE_TAP_SECS && last_position.distance(position) < slop
+        });
+
+        // A third tap starts a new double-tap rather than completing another one
+        self.last_tap = if self.double_tap { None } else { Some((now, position)) };
+        self.press_start = Some((now, position));
+    }
+}
+
+/// Turns mouse and touch input into press, drag, drop and quick move actions, recognising double-taps and drags
+pub fn pointer_input_system(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    window_query: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    time: Res<Time>,
+    mut game_actions: MessageWriter<GameAction>,
+    mut pointer: Local<Pointer>,
+) {
+    let Ok(window) = window_query.single() else { return };
+    let Ok((camera, camera_transform)) = camera_query.single() else { return };
+    let to_world = |viewport_pos: Vec2| camera.viewport_to_world_2d(camera_transform, viewport_pos).ok();
+    let now = time.elapsed_secs_f64();
+
+    pointer.just_pressed = false;
+    pointer.just_released = false;
+    pointer.double_tap = false;
+
+    if let Some(id) = pointer.touch_id {
+        // Follow the finger until it lifts
+        if let Some(touch) = touches.get_pressed(id) {
+            pointer.position = to_world(touch.position()).or(pointer.position);
+        } else {
+            if let Some(touch) = touches.get_released(id) {
+                pointer.position = to_world(touch.position()).or(pointer.position);
+            }
+            pointer.pressed = false;
+            pointer.just_released = true;
+            pointer.touch_id = None;
+        }
+    } else if let Some(touch) = touches.iter_just_pressed().next() {
+        pointer.touch_id = Some(touch.id());
+        pointer.is_touch = true;
+        pointer.position = to_world(touch.position());
+        if let Some(position) = pointer.position {
+            debug!("Touch pressed at {:?}", position);
+            pointer.press(now, position);
+        }
+    } else {
+        pointer.is_touch = false;
+        pointer.position = window.cursor_position().and_then(to_world).or(pointer.position);
+        if mouse_input.just_pressed(MouseButton::Left) {
+            if let Some(position) = pointer.position {
+                pointer.press(now, position);
+            }
+        }
+        pointer.pressed = mouse_input.pressed(MouseButton::Left);
+        pointer.just_released = mouse_input.just_released(MouseButton::Left);
+    }
+
+    let position = pointer.position;
+    pointer.drag_started = pointer.pressed && pointer.press_start.is_some_and(|(start_time, start_position)| {
+        now - start_time > DRAG_HOLD_SECS || position.is_some_and(|position| position.distance(start_position) > DRAG_SLOP)
+    });
+
+    let Some(position) = pointer.position else { return };
+    if pointer.just_pressed {
+        game_actions.write(GameAction::Press(position));
+        if pointer.double_tap {
+            game_actions.write(GameAction::QuickMove(position));
+        }
+    }
+    if pointer.drag_started {
+        game_actions.write(GameAction::Drag(position));
+    }
+    if pointer.just_released {
+        game_actions.write(GameAction::Drop(position));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_double_click_needs_both_clicks_on_the_same_spot() {
+        let mut pointer = Pointer::default();
+        pointer.press(0.0, Vec2::new(-300.0, 200.0)); // The stock
+        pointer.press(0.2, Vec2::new(0.0, 0.0)); // A card, quickly after
+        assert!(!pointer.double_tap);
+
+        pointer.press(0.4, Vec2::new(2.0, 1.0));
+        assert!(pointer.double_tap);
+    }
+
+    #[test]
+    fn a_double_tap_can_land_a_little_apart() {
+        let mut pointer = Pointer { is_touch: true, ..default() };
+        pointer.press(0.0, Vec2::ZERO);
+        pointer.press(0.3, Vec2::new(20.0, 0.0));
+        assert!(pointer.double_tap);
+
+        // Too slow
+        pointer.press(1.0, Vec2::ZERO);
+        pointer.press(1.6, Vec2::ZERO);
+        assert!(!pointer.double_tap);
+    }
+}
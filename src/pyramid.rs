@@ -0,0 +1,425 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use crate::actions::GameAction;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::layout::Layout;
+use crate::game_mode::spawn_win_message;
+use crate::hit_test::card_contains;
+use crate::sound::{PlaySound, SoundEffect};
+use crate::utils::{shuffle_deck, standard_deck};
+use crate::theme::CardImages;
+use tracing::debug;
+
+// Pyramid board: 7 overlapping rows (28 cards), the stock and waste top right, the discard pile top left
+const PYRAMID_ROWS: usize = 7;
+const PYRAMID_CARDS: usize = PYRAMID_ROWS * (PYRAMID_ROWS + 1) / 2;
+const PYRAMID_ROW_SPACING: f32 = 55.0;
+const PYRAMID_CARD_SPACING: f32 = 90.0;
+
+/// Marks a card dealt into the pyramid with its slot in the PyramidBoard
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PyramidSlot(pub usize);
+
+/// The pyramid as a pile graph: which card, if any, is left in each slot.
+/// Slots are numbered row by row from the top, so row `r`, position `i` is slot `r * (r + 1) / 2 + i`.
+#[derive(Resource, Default)]
+pub struct PyramidBoard(pub Vec<Option<Entity>>);
+
+impl PyramidBoard {
+    pub fn slot_index(row: usize, position: usize) -> usize {
+        row * (row + 1) / 2 + position
+    }
+
+    /// Row and position within the row for a slot
+    pub fn row_and_position(slot: usize) -> (usize, usize) {
+        let mut row = 0;
+        while PyramidBoard::slot_index(row + 1, 0) <= slot {
+            row += 1;
+        }
+        (row, slot - PyramidBoard::slot_index(row, 0))
+    }
+
+    /// A card is exposed once both cards overlapping it from the row below are gone
+    pub fn is_exposed(&self, slot: usize) -> bool {
+        let (row, position) = PyramidBoard::row_and_position(slot);
+        if row + 1 >= PYRAMID_ROWS {
+            return true;
+        }
+
+        let is_empty = |slot: usize| self.0.get(slot).is_none_or(|card| card.is_none());
+        is_empty(PyramidBoard::slot_index(row + 1, position)) && is_empty(PyramidBoard::slot_index(row + 1, position + 1))
+    }
+
+    pub fn is_cleared(&self) -> bool {
+        self.0.iter().all(|card| card.is_none())
+    }
+}
+
+/// The cards removed in pairs, in the order they went. Kept apart from FoundationPiles: Pyramid has no
+/// foundations, and 13 discarded cards must not look like a finished foundation to the win check.
+#[derive(Resource, Default)]
+pub struct PyramidDiscard(pub Vec<CardId>);
+
+/// Spawns the Pyramid board: 28 face-up cards in a triangle, the other 24 in the stock
+pub fn setup_pyramid_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    let apex_y = Layout::TOP_ROW_Y - 20.0;
+
+    let mut deck = standard_deck(0);
+    shuffle_deck(&mut deck, deal_number as u64);
+
+    // Deal the pyramid row by row, each row drawn over the one above it
+    let mut cards = deck.into_iter();
+    let mut board = vec![None; PYRAMID_CARDS];
+    for row in 0..PYRAMID_ROWS {
+        let row_start_x = -(row as f32 * PYRAMID_CARD_SPACING) / 2.0;
+        for position in 0..=row {
+            let Some(card) = cards.next() else { break };
+            let slot = PyramidBoard::slot_index(row, position);
+            let card_position = Vec3::new(
+                row_start_x + (position as f32 * PYRAMID_CARD_SPACING),
+                apex_y - (row as f32 * PYRAMID_ROW_SPACING),
+                (row + 1) as f32,
+            );
+
+            let entity = create_card_entity(
+                commands,
+                card_images,
+                card_position,
+                card,
+                true,
+                (
+                    PyramidSlot(slot),
+                    CardFront,
+                    OriginalPosition(card_position),
+                ),
+            );
+            board[slot] = Some(entity);
+        }
+    }
+
+    // Waste pile placeholder, where the Klondike stock click deals to
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.3, 0.3, 0.3),
+            custom_size: Some(CARD_SIZE),
+            ..default()
+        },
+        Transform::from_translation(WastePosition::default().0),
+        WastePile,
+    ));
+
+    // Stock pile visual
+    create_card_entity(
+        commands,
+        card_images,
+        StockPosition::default().0,
+        CardId::new(0, CardSuit::Spades, 1), // Dummy card - not important for stock pile
+        false,
+        (
+            StockPile,
+            CardBack,
+        ),
+    );
+
+    // Removed pairs go face-up onto a single discard pile
+    let discard_position = Layout::top_row_position(0, Layout::KLONDIKE_COLUMNS);
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.2, 0.2, 0.2),
+            custom_size: Some(CARD_SIZE),
+            ..default()
+        },
+        Transform::from_translation(discard_position),
+        FoundationPile,
+    ));
+
+    commands.insert_resource(StockCards(cards.collect()));
+    commands.insert_resource(PyramidBoard(board));
+    commands.insert_resource(TableauPositions(Vec::new()));
+    commands.insert_resource(FoundationPositions(vec![discard_position]));
+    commands.insert_resource(FoundationPiles(Vec::new())); // No foundations, only the discard pile
+    commands.insert_resource(PyramidDiscard::default());
+}
+
+/// What the pointer is doing with Pyramid cards, kept between frames
+#[derive(Default)]
+pub struct PyramidInput {
+    pressed: Option<(Entity, Vec3, Vec2)>, // Card under the press, where it was, and where the press started
+    dragging: bool,
+    selected: Option<Entity>, // First card of a click-to-pair
+}
+
+/// What removing a pair touches: the pyramid, the discard pile, the sound it makes and the undo history
+#[derive(SystemParam)]
+pub struct PyramidPlay<'w> {
+    pub sounds: MessageWriter<'w, PlaySound>,
+    pub moves: MessageWriter<'w, MoveMade>,
+    pub board: ResMut<'w, PyramidBoard>,
+    pub discard: ResMut<'w, PyramidDiscard>,
+    pub foundation_positions: Res<'w, FoundationPositions>,
+}
+
+/// The cards Pyramid plays with: where each one lies, its tint, and its pyramid slot or place on the waste
+pub type PyramidCards<'w, 's> = Query<'w, 's, (Entity, &'static mut Transform, &'static CardData, &'static mut Sprite, Option<&'static PyramidSlot>, Option<&'static WastePile>), With<Card>>;
+
+/// Pyramid input: drag one card onto another to pair them, or click one card then the other.
+/// Pairs must add up to 13 (Jack 11, Queen 12), and a King is removed with a single click.
+pub fn pyramid_input_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut play: PyramidPlay,
+    mut card_query: PyramidCards,
+    mut input: Local<PyramidInput>,
+) {
+    // A quick click can press and release in the same frame, so every action is played through in order
+    for action in game_actions.read() {
+        pyramid_pointer_action(*action, &mut commands, &mut play, &mut card_query, &mut input);
+    }
+}
+
+/// Handles one pointer press, drag or drop on the Pyramid board
+fn pyramid_pointer_action(
+    action: GameAction,
+    commands: &mut Commands,
+    play: &mut PyramidPlay,
+    card_query: &mut PyramidCards,
+    input: &mut PyramidInput,
+) {
+    let cursor_world_pos = match action {
+        GameAction::Press(position) | GameAction::Drag(position) | GameAction::Drop(position) => position,
+        _ => return,
+    };
+    let PyramidPlay { sounds, board: pyramid_board, .. } = &mut *play;
+
+    // Playable cards: exposed pyramid cards and the top waste card
+    let top_waste = card_query
+        .iter()
+        .filter(|(_entity, _transform, _card_data, _sprite, _slot, waste)| waste.is_some())
+        .max_by(|a, b| a.1.translation.z.partial_cmp(&b.1.translation.z).unwrap())
+        .map(|(entity, ..)| entity);
+    let is_playable = |entity: Entity, slot: Option<&PyramidSlot>| match slot {
+        Some(slot) => pyramid_board.is_exposed(slot.0),
+        None => Some(entity) == top_waste,
+    };
+
+    let playable_under_cursor = |exclude: Option<Entity>| {
+        card_query
+            .iter()
+            .filter(|(entity, transform, _card_data, _sprite, slot, _waste)| {
+                Some(*entity) != exclude
+                    && is_playable(*entity, *slot)
+                    && card_contains(transform.translation, cursor_world_pos)
+            })
+            .max_by(|a, b| a.1.translation.z.partial_cmp(&b.1.translation.z).unwrap())
+            .map(|(entity, _transform, card_data, ..)| (entity, card_data.value))
+    };
+
+    if let GameAction::Press(_) = action {
+        input.pressed = None;
+        if let Some((entity, _value)) = playable_under_cursor(None) {
+            if let Ok((_entity, transform, ..)) = card_query.get(entity) {
+                input.pressed = Some((entity, transform.translation, cursor_world_pos));
+                input.dragging = false;
+            }
+        }
+        return;
+    }
+
+    let Some((pressed_entity, original_position, press_pos)) = input.pressed else { return };
+
+    if let GameAction::Drag(_) = action {
+        // Start dragging once the pointer has moved far enough for it not to be a click
+        if !input.dragging && (cursor_world_pos - press_pos).length() > 10.0 {
+            input.dragging = true;
+            commands.entity(pressed_entity).insert(CurrentlyDragging);
+            sounds.write(PlaySound(SoundEffect::PickUp));
+            set_highlight(card_query, input.selected.take(), false);
+        }
+        if input.dragging {
+            if let Ok((_entity, mut transform, ..)) = card_query.get_mut(pressed_entity) {
+                transform.translation = Vec3::new(cursor_world_pos.x, cursor_world_pos.y, 50.0);
+            }
+        }
+        return;
+    }
+
+    // Otherwise the pointer has been released
+    input.pressed = None;
+    commands.entity(pressed_entity).remove::<CurrentlyDragging>();
+    let Ok((_entity, _transform, pressed_card, ..)) = card_query.get(pressed_entity) else { return };
+    let pressed_value = pressed_card.value;
+
+    let pair = if input.dragging {
+        // Dropped onto the other half of a pair?
+        let target = playable_under_cursor(Some(pressed_entity))
+            .filter(|(_target, value)| pressed_value + value == 13)
+            .map(|(target, _value)| target);
+        if target.is_none() {
+            debug!("Pyramid drop is not a pair - snapping back");
+            sounds.write(PlaySound(SoundEffect::Invalid));
+            if let Ok((_entity, mut transform, ..)) = card_query.get_mut(pressed_entity) {
+                transform.translation = original_position;
+            }
+        }
+        target.map(|target| vec![pressed_entity, target])
+    } else if pressed_value == 13 {
+        // Kings go on their own
+        set_highlight(card_query, input.selected.take(), false);
+        Some(vec![pressed_entity])
+    } else {
+        match input.selected.take() {
+            Some(selected) if selected != pressed_entity => {
+                let selected_value = card_query.get(selected).map(|(_entity, _transform, card_data, ..)| card_data.value).unwrap_or(0);
+                set_highlight(card_query, Some(selected), false);
+                if selected_value + pressed_value == 13 {
+                    Some(vec![selected, pressed_entity])
+                } else {
+                    // Not a pair - the new card becomes the selection
+                    set_highlight(card_query, Some(pressed_entity), true);
+                    input.selected = Some(pressed_entity);
+                    None
+                }
+            }
+            Some(selected) => {
+                // Clicking the selected card again deselects it
+                set_highlight(card_query, Some(selected), false);
+                None
+            }
+            None => {
+                set_highlight(card_query, Some(pressed_entity), true);
+                input.selected = Some(pressed_entity);
+                None
+            }
+        }
+    };
+    input.dragging = false;
+
+    if let Some(pair) = pair {
+        remove_pair(commands, play, card_query, &pair);
+    }
+}
+
+/// Moves a pair adding up to 13, or a lone King, from the pyramid or waste onto the discard pile.
+/// Shared by the mouse and the pile cursor.
+pub fn remove_pair(commands: &mut Commands, play: &mut PyramidPlay, card_query: &PyramidCards, pair: &[Entity]) {
+    let PyramidPlay { sounds, moves, board: pyramid_board, discard: pyramid_discard, foundation_positions } = play;
+    let Some(discard_position) = foundation_positions.0.first().copied() else { return };
+    sounds.write(PlaySound(SoundEffect::Foundation));
+    moves.write(MoveMade);
+
+    for entity in pair {
+        let Ok((_entity, _transform, card_data, _sprite, slot, _waste)) = card_query.get(*entity) else { continue };
+        debug!("Pyramid: removing {:?} {}", card_data.suit, card_data.value);
+
+        if let Some(slot) = slot {
+            pyramid_board.0[slot.0] = None;
+        }
+        pyramid_discard.0.push(card_data.id());
+
+        let new_position = Vec3::new(discard_position.x, discard_position.y, pyramid_discard.0.len() as f32);
+        commands.entity(*entity)
+            .insert(Transform::from_translation(new_position))
+            .insert(OriginalPosition(new_position))
+            .insert(FoundationPile)
+            .remove::<PyramidSlot>()
+            .remove::<WastePile>()
+            .remove::<Draggable>();
+    }
+}
+
+/// Tints the card picked as the first half of a pair
+pub fn set_highlight(
+    card_query: &mut PyramidCards,
+    entity: Option<Entity>,
+    highlighted: bool,
+) {
+    let Some(entity) = entity else { return };
+    if let Ok((_entity, _transform, _card_data, mut sprite, ..)) = card_query.get_mut(entity) {
+        sprite.color = if highlighted { Color::srgb(1.0, 1.0, 0.6) } else { Color::WHITE };
+    }
+}
+
+/// Pyramid is won once the whole pyramid has been cleared
+pub fn pyramid_win_check_system(
+    mut commands: Commands,
+    pyramid_board: Res<PyramidBoard>,
+    win_message_query: Query<(), With<WinMessage>>,
+) {
+    if !pyramid_board.is_changed() || !win_message_query.is_empty() || pyramid_board.0.is_empty() {
+        return;
+    }
+
+    if pyramid_board.is_cleared() {
+        debug!("Pyramid cleared - game won");
+        spawn_win_message(&mut commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use crate::game_mode::win_check_system;
+
+    // A Pyramid game part-way through: the piles as the setup leaves them, `removed` pyramid slots
+    // cleared and that many cards on the discard pile
+    fn pyramid_world(removed: usize) -> World {
+        let mut world = World::new();
+        let board = (0..PYRAMID_CARDS).map(|slot| (slot >= removed).then(|| Entity::from_raw_u32(slot as u32 + 1).unwrap())).collect();
+        world.insert_resource(PyramidBoard(board));
+        world.insert_resource(FoundationPiles(Vec::new()));
+        world.insert_resource(PyramidDiscard(standard_deck(0)[..removed].to_vec()));
+        world
+    }
+
+    fn won(world: &mut World) -> bool {
+        world.run_system_once(win_check_system).unwrap();
+        world.run_system_once(pyramid_win_check_system).unwrap();
+        world.query_filtered::<(), With<WinMessage>>().iter(world).next().is_some()
+    }
+
+    #[test]
+    fn discarding_thirteen_cards_is_not_a_win() {
+        assert!(!won(&mut pyramid_world(13)));
+        assert!(!won(&mut pyramid_world(26)));
+    }
+
+    #[test]
+    fn clearing_the_pyramid_is_a_win() {
+        assert!(won(&mut pyramid_world(PYRAMID_CARDS)));
+    }
+
+    fn full_board() -> PyramidBoard {
+        PyramidBoard((0..PYRAMID_CARDS).map(|slot| Some(Entity::from_raw_u32(slot as u32 + 1).unwrap())).collect())
+    }
+
+    #[test]
+    fn slots_are_numbered_row_by_row() {
+        assert_eq!(PyramidBoard::slot_index(0, 0), 0);
+        assert_eq!(PyramidBoard::slot_index(2, 1), 4);
+        assert_eq!(PyramidBoard::row_and_position(4), (2, 1));
+        assert_eq!(PyramidBoard::row_and_position(PYRAMID_CARDS - 1), (PYRAMID_ROWS - 1, PYRAMID_ROWS - 1));
+    }
+
+    #[test]
+    fn only_the_bottom_row_starts_exposed() {
+        let board = full_board();
+        let bottom_row = PyramidBoard::slot_index(PYRAMID_ROWS - 1, 0);
+        for slot in 0..PYRAMID_CARDS {
+            assert_eq!(board.is_exposed(slot), slot >= bottom_row, "slot {}", slot);
+        }
+    }
+
+    #[test]
+    fn a_card_is_exposed_once_both_cards_below_it_are_gone() {
+        let mut board = full_board();
+        let slot = PyramidBoard::slot_index(5, 2);
+
+        board.0[PyramidBoard::slot_index(6, 2)] = None;
+        assert!(!board.is_exposed(slot)); // Still half covered
+        board.0[PyramidBoard::slot_index(6, 3)] = None;
+        assert!(board.is_exposed(slot));
+        assert!(!board.is_exposed(PyramidBoard::slot_index(5, 1))); // Its neighbour is still covered on the left
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+/// Where the player's choices are kept between runs: `key = value` lines in barn/settings.txt in the
+/// user's config folder
+fn settings_path() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| std::env::var_os("APPDATA")) // Windows
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("barn").join("settings.txt"))
+}
+
+fn read_settings() -> Vec<(String, String)> {
+    let Some(contents) = settings_path().and_then(|path| std::fs::read_to_string(path).ok()) else { return Vec::new() };
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// The value following `flag` on the command line, or else the one saved under `key`. The command line wins
+/// so a choice can be tried out for one run.
+pub fn setting(flag: &str, key: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| saved_setting(key))
+}
+
+/// The value saved under `key`, for settings whose command line flag takes no value
+pub fn saved_setting(key: &str) -> Option<String> {
+    read_settings().into_iter().find(|(saved_key, _value)| saved_key == key).map(|(_key, value)| value)
+}
+
+/// Remembers a choice for next time. Failing to save only costs the player the choice, so it is just logged.
+pub fn save_setting(key: &str, value: &str) {
+    let Some(path) = settings_path() else {
+        warn!("Nowhere to save settings: no config folder");
+        return;
+    };
+
+    let mut settings = read_settings();
+    match settings.iter_mut().find(|(saved_key, _value)| saved_key == key) {
+        Some(setting) => setting.1 = value.to_string(),
+        None => settings.push((key.to_string(), value.to_string())),
+    }
+    let contents: String = settings.iter().map(|(key, value)| format!("{} = {}\n", key, value)).collect();
+
+    let saved = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(&path, contents));
+    match saved {
+        Ok(()) => debug!("Saved {} = {} to {}", key, value, path.display()),
+        Err(error) => warn!("Couldn't save settings to {}: {}", path.display(), error),
+    }
+}
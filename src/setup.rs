@@ -1,53 +1,45 @@
 use bevy::prelude::*;
 use crate::components::*;
-
+use crate::game_mode::{GameMode, DealNumber, mode_label_text};
 use crate::init_setup::setup_initial_tableau_and_stock;
+use crate::freecell::setup_freecell_board;
+use crate::spider::setup_spider_board;
+use crate::pyramid::setup_pyramid_board;
+use crate::golf::{setup_golf_board, setup_tripeaks_board};
+use crate::yukon::setup_yukon_board;
+use crate::canfield::setup_canfield_board;
+use crate::double_klondike::setup_double_klondike_board;
+use crate::cursor::{spawn_cursor_highlight, PileCursor};
+use crate::layout::{Layout, ScreenAnchor};
+use crate::theme::CardImages;
 
-pub fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>, stock_cards: ResMut<StockCards>) {
-    
-    // Spawn a 2D camera
-    commands.spawn(Camera2d::default());
-    
-    // Stock pile will be created by setup_initial_tableau_and_stock function
+/// Spawns the 2D camera (the layout system zooms it to fit the window). It is there from the start
+/// so the loading screen can be seen.
+pub fn spawn_camera(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
 
-    // Create waste pile above Stack 6
-    let waste_x = -(6.0 * 100.0) / 2.0 + (5.0 * 100.0); // Above Stack 6 (x = 200)
-    let waste_y = WINDOW_HEIGHT / 2.0 - 100.0; // Aligned with Stock Pile and Foundation Piles
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.3, 0.3, 0.3),
-            custom_size: Some(Vec2::new(80.0, 120.0)),
-            ..default()
-        },
-        Transform::from_xyz(waste_x, waste_y, 0.0),
-        WastePile,
-    ));
-
-    // Create foundation piles above the first 4 stack positions
-    let foundation_start_x = -(6.0 * 100.0) / 2.0; // Same starting X as tableau stacks
-    for i in 0..4 {
-        let x_pos = foundation_start_x + (i as f32 * 100.0);
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(0.2, 0.2, 0.2),
-                custom_size: Some(Vec2::new(80.0, 120.0)),
-                ..default()
-            },
-            Transform::from_xyz(x_pos, WINDOW_HEIGHT / 2.0 - 100.0, 0.0), // Aligned with Stock Pile
-            FoundationPile,
-        ));
-    }
+pub fn setup_game(mut commands: Commands, card_images: CardImages, game_mode: Res<GameMode>, deal_number: Res<DealNumber>) {
 
-    // Set up the initial tableau and stock pile distribution
-    setup_initial_tableau_and_stock(&mut commands, asset_server, stock_cards);
+    // Set up the piles and cards for the selected game mode
+    setup_board(&mut commands, &card_images, *game_mode, *deal_number);
 
     // Score display
     commands.spawn((
         Text2d::new("Score: 0"),
-        Transform::from_xyz(-WINDOW_WIDTH / 2.0 + 100.0, WINDOW_HEIGHT / 2.0 - 50.0, 2.0),
+        Transform::from_xyz(0.0, 0.0, 2.0),
+        ScreenAnchor { anchor: Vec2::new(-1.0, 1.0), offset: Vec2::new(100.0, -50.0) }, // Top left corner
         Score,
     ));
 
+    // Game mode and deal number
+    commands.spawn((
+        Text2d::new(mode_label_text(*game_mode, *deal_number)),
+        Transform::from_xyz(0.0, 0.0, 2.0),
+        ScreenAnchor { anchor: Vec2::new(0.0, 1.0), offset: Vec2::new(0.0, -20.0) }, // Top middle
+        ModeLabel,
+    ));
+
     // Undo button
     commands.spawn((
         Sprite {
@@ -55,14 +47,94 @@ pub fn setup_game(mut commands: Commands, asset_server: Res<AssetServer>, stock_
             custom_size: Some(Vec2::new(100.0, 40.0)),
             ..default()
         },
-        Transform::from_xyz(WINDOW_WIDTH / 2.0 - 100.0, WINDOW_HEIGHT / 2.0 - 50.0, 2.0),
+        Transform::from_xyz(0.0, 0.0, 2.0),
+        ScreenAnchor { anchor: Vec2::new(1.0, 1.0), offset: Vec2::new(-100.0, -50.0) }, // Top right corner
         UndoButton,
     ));
 
+    // Keyboard and gamepad cursor (hidden until one of them is used)
+    spawn_cursor_highlight(&mut commands);
+
     // Undo button text
     commands.spawn((
         Text2d::new("Undo"),
-        Transform::from_xyz(WINDOW_WIDTH / 2.0 - 100.0, WINDOW_HEIGHT / 2.0 - 50.0, 2.0),
+        Transform::from_xyz(0.0, 0.0, 3.0),
+        ScreenAnchor { anchor: Vec2::new(1.0, 1.0), offset: Vec2::new(-100.0, -50.0) },
     ));
 
-} 
+}
+
+/// Resets the per-game resources and deals a fresh board for the given mode.
+/// Used both at startup and when the player starts a new game.
+pub fn setup_board(commands: &mut Commands, card_images: &CardImages, game_mode: GameMode, deal_number: DealNumber) {
+    commands.insert_resource(StockCards(Vec::new()));
+    commands.insert_resource(FoundationPiles(vec![Vec::new(); 4]));
+    commands.insert_resource(FreeCells(Vec::new()));
+    commands.insert_resource(FreeCellPositions(Vec::new()));
+    commands.insert_resource(UndoStack(Vec::new()));
+    commands.insert_resource(RedoStack(Vec::new()));
+    commands.insert_resource(SelectedCard(None));
+    commands.insert_resource(GameScore(0));
+    commands.insert_resource(RankRules::default());
+    commands.insert_resource(StockPosition::default());
+    commands.insert_resource(WastePosition::default());
+    commands.insert_resource(ReservePosition::default());
+    commands.insert_resource(PileCursor::default());
+
+    match game_mode {
+        GameMode::Klondike | GameMode::Ferris => setup_klondike_board(commands, card_images),
+        GameMode::DoubleKlondike => setup_double_klondike_board(commands, card_images, deal_number.0),
+        GameMode::FreeCell => setup_freecell_board(commands, card_images, deal_number.0),
+        GameMode::Spider(suits) => setup_spider_board(commands, card_images, suits, deal_number.0),
+        GameMode::Pyramid => setup_pyramid_board(commands, card_images, deal_number.0),
+        GameMode::Golf => setup_golf_board(commands, card_images, deal_number.0),
+        GameMode::TriPeaks => setup_tripeaks_board(commands, card_images, deal_number.0),
+        GameMode::Yukon | GameMode::Russian => setup_yukon_board(commands, card_images, deal_number.0),
+        GameMode::Canfield => setup_canfield_board(commands, card_images, deal_number.0),
+    }
+}
+
+fn setup_klondike_board(commands: &mut Commands, card_images: &CardImages) {
+    // Stock pile will be created by setup_initial_tableau_and_stock function
+
+    // Create waste pile above Stack 6, aligned with the stock pile and foundation piles
+    commands.spawn((
+        Sprite {
+            color: Color::srgb(0.3, 0.3, 0.3),
+            custom_size: Some(CARD_SIZE),
+            ..default()
+        },
+        Transform::from_translation(WastePosition::default().0),
+        WastePile,
+    ));
+
+    setup_foundations(commands);
+
+    // Set up the initial tableau and stock pile distribution
+    setup_initial_tableau_and_stock(commands, card_images);
+}
+
+/// Spawns the 4 foundation piles above the first 4 Klondike stacks
+pub fn setup_foundations(commands: &mut Commands) {
+    // Create foundation piles above the first 4 stack positions
+    spawn_foundation_row(commands, Layout::KLONDIKE_COLUMNS, 4);
+}
+
+/// Spawns `count` foundation piles at the left of the top row, on the grid of a board `columns` wide
+pub fn spawn_foundation_row(commands: &mut Commands, columns: usize, count: usize) {
+    let mut foundation_positions = Vec::new();
+    for i in 0..count {
+        let position = Layout::top_row_position(i, columns); // Aligned with Stock Pile
+        commands.spawn((
+            Sprite {
+                color: Color::srgb(0.2, 0.2, 0.2),
+                custom_size: Some(CARD_SIZE),
+                ..default()
+            },
+            Transform::from_translation(position),
+            FoundationPile,
+        ));
+        foundation_positions.push(position);
+    }
+    commands.insert_resource(FoundationPositions(foundation_positions));
+}
@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use bevy::audio::Volume;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use crate::card_validation::{DropTarget, MoveError};
+use crate::components::WinMessage;
+use crate::feedback::{spawn_toast, MoveRejected};
+use crate::settings::{save_setting, saved_setting, setting};
+use tracing::debug;
+
+// A sound that hasn't started after this long never will (there is no audio output device), so it is cleared away
+const UNPLAYED_SOUND_SECS: f32 = 5.0;
+const VOLUME_STEP: f32 = 0.1; // - and = turn the master volume down and up by this much
+
+/// The game's sound effects, each bundled as a short clip in assets/sounds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundEffect {
+    PickUp,
+    Drop,
+    Invalid,
+    Flip,
+    Deal,
+    Recycle,
+    Foundation,
+    Win,
+}
+
+impl SoundEffect {
+    const ALL: [SoundEffect; 8] = [
+        SoundEffect::PickUp,
+        SoundEffect::Drop,
+        SoundEffect::Invalid,
+        SoundEffect::Flip,
+        SoundEffect::Deal,
+        SoundEffect::Recycle,
+        SoundEffect::Foundation,
+        SoundEffect::Win,
+    ];
+
+    fn path(self) -> &'static str {
+        match self {
+            SoundEffect::PickUp => "sounds/pickup.wav",
+            SoundEffect::Drop => "sounds/drop.wav",
+            SoundEffect::Invalid => "sounds/invalid.wav",
+            SoundEffect::Flip => "sounds/flip.wav",
+            SoundEffect::Deal => "sounds/deal.wav",
+            SoundEffect::Recycle => "sounds/recycle.wav",
+            SoundEffect::Foundation => "sounds/foundation.wav",
+            SoundEffect::Win => "sounds/win.wav",
+        }
+    }
+
+    /// The sound of cards landing on `target`
+    pub fn placed_on(target: DropTarget) -> Self {
+        match target {
+            DropTarget::Foundation(..) => SoundEffect::Foundation,
+            DropTarget::FreeCell(..) | DropTarget::Tableau(_) => SoundEffect::Drop,
+        }
+    }
+}
+
+/// Asks for a sound effect to be played. The same sound asked for several times in one frame plays once.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PlaySound(pub SoundEffect);
+
+/// Volume settings: `master` scales everything the game plays and `effects` the sound effects on top of
+/// that, both from 0 to 1. Set with --volume, --effects-volume and --mute; in the game S turns the sound
+/// on and off and - and = turn it down and up, which is remembered for next time.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct SoundSettings {
+    pub master: f32,
+    pub effects: f32,
+    pub muted: bool,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        SoundSettings { master: 1.0, effects: 0.7, muted: false }
+    }
+}
+
+impl SoundSettings {
+    /// The settings given on the command line, or else the ones saved last time, with the defaults for anything left out
+    pub fn load() -> Self {
+        let defaults = SoundSettings::default();
+        let volume = |flag: &str, key: &str| setting(flag, key).and_then(|volume| volume.parse::<f32>().ok()).map(|volume| volume.clamp(0.0, 1.0));
+
+        SoundSettings {
+            master: volume("--volume", "volume").unwrap_or(defaults.master),
+            effects: volume("--effects-volume", "effects_volume").unwrap_or(defaults.effects),
+            muted: std::env::args().skip(1).any(|arg| arg == "--mute") || saved_setting("muted").as_deref() == Some("true"),
+        }
+    }
+}
+
+/// The master volume one step up or down, kept to whole tenths so repeated steps land back on the same values
+pub fn step_volume(volume: f32, step: f32) -> f32 {
+    (((volume + step) * 10.0).round() / 10.0).clamp(0.0, 1.0)
+}
+
+/// The loaded sound effect clips, in the order of `SoundEffect::ALL`
+#[derive(Resource)]
+pub struct Sounds(Vec<Handle<AudioSource>>);
+
+impl Sounds {
+    fn get(&self, effect: SoundEffect) -> Option<Handle<AudioSource>> {
+        let index = SoundEffect::ALL.iter().position(|other| *other == effect)?;
+        self.0.get(index).cloned()
+    }
+}
+
+/// A sound effect that has been started, and how long ago
+#[derive(Component)]
+pub struct SoundEffectPlayer {
+    age: f32,
+}
+
+/// Loads every sound effect up front so the first of each plays without a delay
+pub fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = SoundEffect::ALL.iter().map(|effect| asset_server.load(effect.path())).collect();
+    commands.insert_resource(Sounds(handles));
+}
+
+/// S mutes and unmutes, and - and = turn the master volume down and up. Both are saved for next time, and
+/// the master volume is handed to Bevy's global volume whenever it changes.
+pub fn sound_settings_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut sound_settings: ResMut<SoundSettings>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyS) {
+        sound_settings.muted = !sound_settings.muted;
+        debug!("Sound {}", if sound_settings.muted { "muted" } else { "on" });
+        save_setting("muted", &sound_settings.muted.to_string());
+        spawn_toast(&mut commands, if sound_settings.muted { "Sound off" } else { "Sound on" }.to_string());
+    }
+
+    let step = if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        -VOLUME_STEP
+    } else if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        VOLUME_STEP
+    } else {
+        0.0
+    };
+    if step != 0.0 {
+        sound_settings.master = step_volume(sound_settings.master, step);
+        sound_settings.muted = false; // Turning the volume up or down is asking to hear it
+        debug!("Volume {}", sound_settings.master);
+        save_setting("volume", &sound_settings.master.to_string());
+        save_setting("muted", "false");
+        spawn_toast(&mut commands, format!("Volume: {}%", (sound_settings.master * 100.0).round()));
+    }
+
+    if sound_settings.is_changed() {
+        global_volume.volume = Volume::Linear(sound_settings.master);
+    }
+}
+
+/// Plays the sounds asked for this frame, plus a buzz for every turned-down move and a fanfare when the game is won
+pub fn play_sounds_system(
+    mut commands: Commands,
+    mut sound_requests: MessageReader<PlaySound>,
+    mut move_rejections: MessageReader<MoveRejected>,
+    win_message_query: Query<(), Added<WinMessage>>,
+    sounds: Res<Sounds>,
+    sound_settings: Res<SoundSettings>,
+) {
+    let mut effects: Vec<SoundEffect> = sound_requests.read().map(|request| request.0).collect();
+    effects.extend(move_rejections.read().map(|rejection| match rejection.error {
+        MoveError::NoTarget => SoundEffect::Drop, // Dropped on the felt, the cards just go back
+        _ => SoundEffect::Invalid,
+    }));
+    if !win_message_query.is_empty() {
+        effects.push(SoundEffect::Win);
+    }
+
+    if sound_settings.muted {
+        return;
+    }
+
+    for (i, effect) in effects.iter().enumerate() {
+        if effects[..i].contains(effect) {
+            continue; // Already playing this frame
+        }
+        let Some(handle) = sounds.get(*effect) else { continue };
+
+        debug!("Playing sound {:?}", effect);
+        commands.spawn((
+            AudioPlayer(handle),
+            PlaybackSettings::DESPAWN.with_volume(Volume::Linear(sound_settings.effects)),
+            SoundEffectPlayer { age: 0.0 },
+        ));
+    }
+}
+
+/// Bevy removes sound effects once they have finished, but without an audio output device they are never
+/// started. Those are removed here instead of piling up.
+pub fn unplayed_sounds_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut players_query: Query<(Entity, &mut SoundEffectPlayer), Without<AudioSink>>,
+) {
+    for (entity, mut player) in players_query.iter_mut() {
+        player.age += time.delta_secs();
+        if player.age > UNPLAYED_SOUND_SECS {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_steps_land_on_whole_tenths() {
+        let mut volume = 0.7;
+        for _ in 0..3 {
+            volume = step_volume(volume, VOLUME_STEP);
+        }
+        assert_eq!(volume, 1.0);
+        for _ in 0..4 {
+            volume = step_volume(volume, -VOLUME_STEP);
+        }
+        assert_eq!(volume, 0.6);
+        assert_eq!(step_volume(0.33, VOLUME_STEP), 0.4);
+    }
+
+    #[test]
+    fn volume_steps_stop_at_silent_and_full() {
+        assert_eq!(step_volume(1.0, VOLUME_STEP), 1.0);
+        assert_eq!(step_volume(0.0, -VOLUME_STEP), 0.0);
+        assert_eq!(step_volume(0.04, -VOLUME_STEP), 0.0);
+    }
+}
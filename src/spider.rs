@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use crate::actions::GameAction;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::layout::Layout;
+use crate::game_mode::{GameMode, SpiderSuits};
+use crate::hit_test::{board_piles, pile_under};
+use crate::sound::{PlaySound, SoundEffect};
+use crate::stock_click::StockDeal;
+use crate::utils::{has_complete_run, shuffle_deck, RunRule};
+use crate::theme::CardImages;
+use tracing::debug;
+
+// Spider board: 10 columns, the stock in the top right corner and 8 foundations along the top row
+const SPIDER_COLUMNS: usize = 10;
+const SPIDER_FOUNDATIONS: usize = 8;
+
+/// Spawns the Spider board: two decks' worth of cards (104) made of the chosen suits,
+/// 54 dealt into 10 columns with only the top card face-up, the other 50 left in the stock
+pub fn setup_spider_board(commands: &mut Commands, card_images: &CardImages, suits: SpiderSuits, deal_number: u32) {
+    let stock_position = Layout::top_row_position(SPIDER_COLUMNS - 1, SPIDER_COLUMNS); // Over the last column
+
+    // Repeat the suits until there are 104 cards (8 runs of Ace to King)
+    let mut deck = Vec::new();
+    for (run, suit) in suits.suits().iter().cycle().take(SPIDER_FOUNDATIONS).enumerate() {
+        for value in 1..=13 {
+            // Each run gets its own deck index so one-suit games don't repeat a CardId
+            deck.push(CardId::new(run as u8, *suit, value));
+        }
+    }
+    shuffle_deck(&mut deck, deal_number as u64);
+
+    // Foundations only ever receive complete King to Ace runs
+    let mut foundation_positions = Vec::new();
+    for i in 0..SPIDER_FOUNDATIONS {
+        let position = Layout::top_row_position(i, SPIDER_COLUMNS);
+        commands.spawn((
+            Sprite {
+                color: Color::srgb(0.2, 0.2, 0.2),
+                custom_size: Some(CARD_SIZE),
+                ..default()
+            },
+            Transform::from_translation(position),
+            FoundationPile,
+        ));
+        foundation_positions.push(position);
+    }
+
+    // The first 4 columns get 6 cards, the other 6 get 5
+    let mut cards = deck.into_iter();
+    for column in 0..SPIDER_COLUMNS {
+        let column_size = if column < 4 { 6 } else { 5 };
+
+        for row in 0..column_size {
+            let Some(card) = cards.next() else { break };
+            let position = Layout::tableau_position(column, SPIDER_COLUMNS, row);
+
+            if row == column_size - 1 {
+                create_card_entity(
+                    commands,
+                    card_images,
+                    position,
+                    card,
+                    true,
+                    (
+                        Draggable,
+                        TableauPile,
+                        CardFront,
+                        OriginalPosition(position),
+                    ),
+                );
+            } else {
+                create_card_entity(
+                    commands,
+                    card_images,
+                    position,
+                    card,
+                    false,
+                    (
+                        TableauPile,
+                        OriginalPosition(position),
+                        CardBack,
+                    ),
+                );
+            }
+        }
+    }
+
+    // Whatever is left (50 cards) waits in the stock, dealt 10 at a time
+    let stock: Vec<CardId> = cards.collect();
+    debug!("Spider deal {}: {} cards left in stock", deal_number, stock.len());
+    commands.insert_resource(StockCards(stock));
+
+    // Stock pile visual
+    create_card_entity(
+        commands,
+        card_images,
+        stock_position,
+        CardId::new(0, CardSuit::Spades, 1), // Dummy card - not important for stock pile
+        false,
+        (
+            StockPile,
+            CardBack,
+        ),
+    );
+
+    let tableau_positions = (0..SPIDER_COLUMNS)
+        .map(|column| Layout::tableau_position(column, SPIDER_COLUMNS, 0))
+        .collect();
+
+    commands.insert_resource(TableauPositions(tableau_positions));
+    commands.insert_resource(FoundationPositions(foundation_positions));
+    commands.insert_resource(StockPosition(stock_position));
+    commands.insert_resource(FoundationPiles(vec![Vec::new(); SPIDER_FOUNDATIONS]));
+}
+
+/// Clicking the Spider stock deals one face-up card onto every column.
+/// As in the classic game, there can be no empty columns when dealing.
+pub fn spider_stock_click_system(
+    mut game_actions: MessageReader<GameAction>,
+    mut commands: Commands,
+    mut deal: StockDeal,
+    game_mode: Res<GameMode>,
+    pile_positions: PilePositions,
+) {
+    let Some(cursor_world_pos) = game_actions.read().filter_map(GameAction::press_position).last() else { return };
+
+    // Check if stock pile was clicked
+    let piles = board_piles(*game_mode, &pile_positions);
+    if pile_under(cursor_world_pos, &piles, &[]) != Some(PileId::Stock) {
+        return;
+    }
+
+    spider_deal_row(&mut commands, &mut deal, &pile_positions.tableau);
+}
+
+/// Deals one face-up card onto every column, unless a column is empty. Shared by the mouse and keyboard input.
+pub fn spider_deal_row(
+    commands: &mut Commands,
+    deal: &mut StockDeal,
+    tableau_positions: &TableauPositions,
+) {
+    let StockDeal { stock_cards, card_images, sounds, moves, tableau_cards, stock_entities, .. } = deal;
+
+    if stock_cards.0.is_empty() {
+        debug!("Spider stock is empty");
+        return;
+    }
+
+    // Find the top card of every column
+    let mut column_tops = Vec::new();
+    for column in tableau_positions.0.iter() {
+        let top = tableau_cards
+            .iter()
+            .map(|transform| transform.translation)
+            .filter(|position| (position.x - column.x).abs() < 5.0)
+            .max_by(|a, b| a.z.partial_cmp(&b.z).unwrap());
+
+        match top {
+            Some(position) => column_tops.push(position),
+            None => {
+                debug!("Spider deal refused: column at x = {} is empty", column.x);
+                return;
+            }
+        }
+    }
+
+    // One card on each column, left to right
+    for top in column_tops {
+        let Some(card) = stock_cards.0.pop() else { break };
+        let position = Vec3::new(top.x, top.y - Layout::FAN_OFFSET, top.z + 1.0);
+        debug!("Dealing {:?} {} onto column at x = {}", card.suit, card.value, top.x);
+
+        create_card_entity(
+            commands,
+            card_images,
+            position,
+            card,
+            true,
+            (
+                Draggable,
+                TableauPile,
+                CardFront,
+                OriginalPosition(position),
+            ),
+        );
+    }
+    sounds.write(PlaySound(SoundEffect::Deal));
+    moves.write(MoveMade);
+
+    // Remove the stock visual once everything has been dealt
+    if stock_cards.0.is_empty() {
+        for entity in stock_entities.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Moves any complete King to Ace run of one suit from the bottom of a column to the next empty foundation
+pub fn spider_complete_run_system(
+    mut commands: Commands,
+    mut sounds: MessageWriter<PlaySound>,
+    selected_card: Res<SelectedCard>,
+    tableau_positions: Res<TableauPositions>,
+    foundation_positions: Res<FoundationPositions>,
+    mut foundation_piles: ResMut<FoundationPiles>,
+    tableau_cards: LyingTableauCards,
+) {
+    // Wait until the player lets go of whatever they're dragging
+    if selected_card.0.is_some() {
+        return;
+    }
+
+    for column in tableau_positions.0.iter() {
+        let mut column_cards: Vec<(Entity, Vec3, &CardData)> = tableau_cards
+            .iter()
+            .filter(|(_entity, transform, _card_data)| (transform.translation.x - column.x).abs() < 5.0)
+            .map(|(entity, transform, card_data)| (entity, transform.translation, card_data))
+            .collect();
+        if column_cards.len() < 13 {
+            continue;
+        }
+
+        // Lowest first, so the last 13 run from the would-be King to the top card
+        column_cards.sort_by(|a, b| a.1.z.partial_cmp(&b.1.z).unwrap());
+        let run = &column_cards[column_cards.len() - 13..];
+
+        if !run.iter().all(|(_entity, _position, card_data)| card_data.is_face_up) {
+            continue;
+        }
+        let run_cards: Vec<CardId> = run.iter().map(|(_entity, _position, card_data)| card_data.id()).collect();
+        if !has_complete_run(&run_cards, RunRule::SameSuit) {
+            continue;
+        }
+
+        let Some(foundation_index) = foundation_piles.0.iter().position(|pile| pile.is_empty()) else { return };
+        let Some(foundation_pos) = foundation_positions.0.get(foundation_index).copied() else { return };
+        debug!("Complete {:?} run in column at x = {} - moving to foundation {}", run_cards[0].suit, column.x, foundation_index);
+
+        for (i, (entity, _position, _card_data)) in run.iter().enumerate() {
+            let new_position = Vec3::new(foundation_pos.x, foundation_pos.y, (i + 1) as f32);
+            commands.entity(*entity)
+                .insert(Transform::from_translation(new_position))
+                .insert(OriginalPosition(new_position))
+                .insert(FoundationPile)
+                .remove::<TableauPile>()
+                .remove::<Draggable>();
+        }
+        foundation_piles.0[foundation_index] = run_cards;
+        sounds.write(PlaySound(SoundEffect::Foundation));
+
+        // Turn over the card the King was sitting on
+        commands.spawn(NeedsFlipUnderneath(run[0].1));
+    }
+}
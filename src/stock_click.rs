@@ -1,107 +1,259 @@
 use bevy::prelude::*;
-use bevy::input::ButtonInput;
-use bevy::input::mouse::MouseButton;
-use bevy::input::keyboard::KeyCode;
+use bevy::ecs::system::SystemParam;
+use crate::actions::GameAction;
 use crate::components::*;
-use crate::utils::get_card_back_image;
 use crate::card_entity::create_card_entity;
+use crate::ferris::apply_recycle_penalty;
+use crate::game_mode::GameMode;
+use crate::golf::GolfBoard;
+use crate::hit_test::{board_piles, pile_under};
+use crate::sound::{PlaySound, SoundEffect};
+use crate::theme::CardImages;
 use tracing::debug;
 
+/// The stock and everything dealing from it touches. Shared by the mouse and cursor deals, and by
+/// the Spider deal onto every column. Each deal is sent on for undo.
+#[derive(SystemParam)]
+pub struct StockDeal<'w, 's> {
+    pub stock_cards: ResMut<'w, StockCards>,
+    pub card_images: CardImages<'w>,
+    pub sounds: MessageWriter<'w, PlaySound>,
+    pub moves: MessageWriter<'w, MoveMade>,
+    pub game_score: ResMut<'w, GameScore>,
+    pub golf_board: ResMut<'w, GolfBoard>,
+    pub waste_cards: Query<'w, 's, (Entity, &'static Transform, &'static CardData, Option<&'static SkippedWasteCard>), With<WastePile>>,
+    pub tableau_cards: Query<'w, 's, &'static Transform, (With<TableauPile>, With<Card>)>,
+    pub stock_entities: Query<'w, 's, Entity, (With<StockPile>, With<Card>)>,
+}
+
 pub fn stock_click_system(
-    mouse_input: Res<ButtonInput<MouseButton>>,
-    window_query: Query<&Window>,
-    mut stock_cards: ResMut<StockCards>,
+    mut game_actions: MessageReader<GameAction>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    waste_cards: Query<(Entity, &Transform, &CardData, Option<&SkippedWasteCard>), With<WastePile>>,
-    _stock_entities: Query<Entity, (With<StockPile>, With<Card>)>,
+    mut deal: StockDeal,
+    game_mode: Res<GameMode>,
+    pile_positions: PilePositions,
 ) {
-    let Ok(window) = window_query.single() else { return };
-    
-    if mouse_input.just_pressed(MouseButton::Left) {
-        if let Some(cursor_pos) = window.cursor_position() {
-            let cursor_world_pos = Vec2::new(
-                cursor_pos.x - window.width() / 2.0,
-                window.height() / 2.0 - cursor_pos.y,
-            );
+    for action in game_actions.read() {
+        if let Some(cursor_world_pos) = action.press_position() {
+            // Check if stock pile was clicked (above Stack 7 at x = 300 unless the mode moved it)
+            let piles = board_piles(*game_mode, &pile_positions);
+            if pile_under(cursor_world_pos, &piles, &[]) == Some(PileId::Stock) {
+                draw_from_stock(&mut commands, &mut deal, *game_mode, pile_positions.waste.0);
+            }
+        }
+    }
+}
 
-            // Check if stock pile was clicked
-            let stock_x = -(6.0 * 100.0) / 2.0 + (6.0 * 100.0); // Above Stack 7 (x = 300)
-            let stock_y = WINDOW_HEIGHT / 2.0 - 100.0; // Above the tableau stacks
-            let stock_bounds = Vec2::new(40.0, 60.0);
-            
-            if (cursor_world_pos - Vec2::new(stock_x, stock_y)).abs().cmplt(stock_bounds).all() {
-                // If stock has cards, deal the top card to waste pile
-                if !stock_cards.0.is_empty() {
-                    // Get and remove the top card from stock
-                    if let Some((suit, value)) = stock_cards.0.pop() {
-                        debug!("Dealing card from stock - value: {}, suit: {:?}", value, suit);
-
-                        
-                        // Create the waste card at the waste pile position
-                        let waste_x = -(6.0 * 100.0) / 2.0 + (5.0 * 100.0); // Above Stack 6 (x = 200)
-                        let waste_y = WINDOW_HEIGHT / 2.0 - 100.0;
-                        
-                        // Find highest Z in waste pile for stacking
-                        let mut highest_z = 0.0;
-                        for (_entity, waste_transform, _card_data, _skipped) in waste_cards.iter() {
-                            if waste_transform.translation.z > highest_z {
-                                highest_z = waste_transform.translation.z;
-                            }
-                        }
-                        
-                        // Create waste card entity
-                        create_card_entity(
-                            &mut commands,
-                            &asset_server,
-                            Vec3::new(waste_x, waste_y, highest_z + 1.0),
-                            suit,
-                            value,
-                            true, // Face up in waste pile
-                            (
-                                WastePile,
-                                CardFront,
-                                Draggable, // Make it draggable
-
-                            ),
-                        );
-                    }
-                } else {
-                    // Stock is empty - recycle waste cards back to stock
-                    debug!("Stock is empty, recycling waste cards back to stock");
-                    
-                    // Safety check: only recycle if there are actually waste cards
-                    if waste_cards.is_empty() {
-                        debug!("No waste cards to recycle, skipping");
-                        return;
-                    }
-                    
-                    // Collect waste card data in the order they were dealt (oldest first)
-                    let mut waste_cards_info: Vec<(Entity, CardSuit, u8, f32)> = waste_cards
-                        .iter()
-                        .map(|(entity, transform, card_data, _)| (entity, card_data.suit, card_data.value, transform.translation.z))
-                        .collect();
-                    
-                    debug!("Found {} waste cards to recycle", waste_cards_info.len());
-                    
-                    // Sort by Z position to ensure correct order (lowest Z = oldest = dealt first)
-                    waste_cards_info.sort_by(|a, b| a.3.partial_cmp(&b.3).unwrap());
-                    
-                    // Put all waste cards back into stock (oldest first, so they'll be dealt last)
-                    let waste_card_data: Vec<(CardSuit, u8)> = waste_cards_info
-                        .iter()
-                        .map(|(_entity, suit, value, _z_pos)| (*suit, *value))
-                        .collect();
-                    
-                    debug!("Recycling {} cards back to stock", waste_card_data.len());
-                    stock_cards.0 = waste_card_data;
-                    
-                    // Now despawn all the waste entities
-                    for (entity, _suit, _value, _z_pos) in &waste_cards_info {
-                        commands.entity(*entity).despawn();
-                    }
-                }
+/// Deals the top card of the stock (three in Canfield) to the waste pile, or turns the waste
+/// back over into the stock once the stock is empty. Shared by the mouse and keyboard input.
+pub fn draw_from_stock(
+    commands: &mut Commands,
+    deal: &mut StockDeal,
+    game_mode: GameMode,
+    waste_position: Vec3,
+) {
+    let StockDeal { stock_cards, card_images, sounds, moves, game_score, golf_board, waste_cards, .. } = deal;
+
+    // If stock has cards, deal the top card (three in Canfield) to waste pile
+    if !stock_cards.0.is_empty() {
+        // Create the waste card at the waste pile position
+        let waste_x = waste_position.x;
+        let waste_y = waste_position.y;
+
+        // Find highest Z in waste pile for stacking
+        let mut highest_z = 0.0;
+        for (entity, waste_transform, _card_data, skipped) in waste_cards.iter() {
+            if waste_transform.translation.z > highest_z {
+                highest_z = waste_transform.translation.z;
             }
+
+            // In Ferris the card being dealt over is skipped until the stock cycles back to it
+            if game_mode == GameMode::Ferris && skipped.is_none() {
+                commands.entity(entity)
+                    .insert(SkippedWasteCard)
+                    .remove::<Draggable>();
+            }
+        }
+
+        for drawn in 0..game_mode.draw_count() {
+            // Get and remove the top card from stock
+            let Some(card) = stock_cards.0.pop() else { break };
+            debug!("Dealing card from stock - value: {}, suit: {:?}", card.value, card.suit);
+
+            // Create waste card entity
+            create_card_entity(
+                commands,
+                card_images,
+                Vec3::new(waste_x, waste_y, highest_z + 1.0 + drawn as f32),
+                card,
+                true, // Face up in waste pile
+                (
+                    WastePile,
+                    CardFront,
+                    Draggable, // Make it draggable
+                ),
+            );
+        }
+        // Drawing from the stock ends a TriPeaks streak
+        golf_board.streak = 0;
+        sounds.write(PlaySound(SoundEffect::Deal));
+        moves.write(MoveMade);
+    } else {
+        // Stock is empty - recycle waste cards back to stock
+        debug!("Stock is empty, recycling waste cards back to stock");
+
+        // Golf and TriPeaks only get one pass through the stock
+        if !game_mode.recycles_stock() {
+            debug!("Stock is used up, no recycling in {:?}", game_mode);
+            return;
+        }
+
+        // Safety check: only recycle if there are actually waste cards
+        if waste_cards.is_empty() {
+            debug!("No waste cards to recycle, skipping");
+            return;
+        }
+
+        // Collect waste card data in the order they were dealt (oldest first)
+        let mut waste_cards_info: Vec<(Entity, CardId, f32)> = waste_cards
+            .iter()
+            .map(|(entity, transform, card_data, _)| (entity, card_data.id(), transform.translation.z))
+            .collect();
+
+        debug!("Found {} waste cards to recycle", waste_cards_info.len());
+
+        // Sort by Z position to ensure correct order (lowest Z = oldest = dealt first)
+        waste_cards_info.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+        // Turn the waste over into the stock, so the oldest card is dealt first again
+        let waste_card_data: Vec<CardId> = waste_cards_info
+            .iter()
+            .map(|(_entity, card, _z_pos)| *card)
+            .collect();
+
+        debug!("Recycling {} cards back to stock", waste_card_data.len());
+        stock_cards.0 = stock_from_waste(waste_card_data);
+        apply_recycle_penalty(game_mode, game_score);
+        sounds.write(PlaySound(SoundEffect::Recycle));
+        moves.write(MoveMade);
+
+        // Now despawn all the waste entities
+        for (entity, _card, _z_pos) in &waste_cards_info {
+            commands.entity(*entity).despawn();
+        }
+    }
+}
+
+/// The stock after the waste (oldest card first) is turned back over. The top of the stock is the end
+/// of the list, so the oldest card comes off first and every pass deals the cards in the same order.
+/// Ferris relies on this: a skipped card waits for the stock to cycle back to it.
+pub fn stock_from_waste(mut waste: Vec<CardId>) -> Vec<CardId> {
+    waste.reverse();
+    waste
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_atlas::CardAtlas;
+    use crate::utils::standard_deck;
+    use bevy::ecs::system::SystemId;
+
+    const STOCK: Vec3 = Vec3::new(-300.0, 200.0, 0.0);
+    const WASTE: Vec3 = Vec3::new(-200.0, 200.0, 0.0);
+
+    // A board with only a stock and a waste pile, and the stock click ready to run on it
+    fn stock_world(game_mode: GameMode, stock: Vec<CardId>) -> (World, SystemId) {
+        let mut world = World::new();
+        world.insert_resource(game_mode);
+        world.insert_resource(CardAtlas::empty());
+        world.insert_resource(StockCards(stock));
+        world.insert_resource(GameScore(0));
+        world.insert_resource(GolfBoard::default());
+        world.insert_resource(TableauPositions(Vec::new()));
+        world.insert_resource(FoundationPositions(Vec::new()));
+        world.insert_resource(FreeCellPositions(Vec::new()));
+        world.insert_resource(StockPosition(STOCK));
+        world.insert_resource(WastePosition(WASTE));
+        world.insert_resource(ReservePosition(None));
+        world.init_resource::<Messages<GameAction>>();
+        world.init_resource::<Messages<PlaySound>>();
+        world.init_resource::<Messages<MoveMade>>();
+        let stock_click = world.register_system(stock_click_system);
+        (world, stock_click)
+    }
+
+    fn click_stock(world: &mut World, stock_click: SystemId) {
+        world.write_message(GameAction::Press(STOCK.truncate()));
+        world.run_system(stock_click).unwrap();
+    }
+
+    // The waste from the bottom card up
+    fn waste(world: &mut World) -> Vec<CardId> {
+        let mut cards: Vec<(f32, CardId)> = world
+            .query_filtered::<(&Transform, &CardData), With<WastePile>>()
+            .iter(world)
+            .map(|(transform, card_data)| (transform.translation.z, card_data.id()))
+            .collect();
+        cards.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        cards.into_iter().map(|(_z, card)| card).collect()
+    }
+
+    #[test]
+    fn every_pass_through_the_stock_deals_the_same_order() {
+        let stock = standard_deck(0)[..5].to_vec();
+        let (mut world, stock_click) = stock_world(GameMode::Klondike, stock.clone());
+
+        for _ in 0..5 {
+            click_stock(&mut world, stock_click);
+        }
+        let first_pass = waste(&mut world);
+        assert_eq!(first_pass.len(), 5);
+        assert_eq!(first_pass[0], stock[4]); // The top of the stock is dealt first
+
+        // Turning the waste over puts the oldest card back on top of the stock
+        click_stock(&mut world, stock_click);
+        assert!(waste(&mut world).is_empty());
+        assert_eq!(world.resource::<StockCards>().0.last(), Some(&first_pass[0]));
+
+        for _ in 0..5 {
+            click_stock(&mut world, stock_click);
+        }
+        assert_eq!(waste(&mut world), first_pass);
+    }
+
+    #[test]
+    fn canfield_deals_three_at_a_time() {
+        let stock = standard_deck(0)[..5].to_vec();
+        let (mut world, stock_click) = stock_world(GameMode::Canfield, stock.clone());
+
+        click_stock(&mut world, stock_click);
+        assert_eq!(waste(&mut world), vec![stock[4], stock[3], stock[2]]);
+        assert_eq!(world.resource::<StockCards>().0, stock[..2].to_vec());
+
+        // Only two are left for the next click
+        click_stock(&mut world, stock_click);
+        assert_eq!(waste(&mut world), vec![stock[4], stock[3], stock[2], stock[1], stock[0]]);
+        assert!(world.resource::<StockCards>().0.is_empty());
+    }
+
+    #[test]
+    fn drawing_ends_a_tripeaks_streak() {
+        let (mut world, stock_click) = stock_world(GameMode::TriPeaks, standard_deck(0)[..2].to_vec());
+        world.resource_mut::<GolfBoard>().streak = 3;
+        click_stock(&mut world, stock_click);
+        assert_eq!(world.resource::<GolfBoard>().streak, 0);
+    }
+
+    #[test]
+    fn golf_does_not_turn_the_waste_back_over() {
+        let stock = standard_deck(0)[..2].to_vec();
+        let (mut world, stock_click) = stock_world(GameMode::Golf, stock.clone());
+
+        for _ in 0..3 {
+            click_stock(&mut world, stock_click);
         }
+        assert_eq!(waste(&mut world), vec![stock[1], stock[0]]);
+        assert!(world.resource::<StockCards>().0.is_empty());
     }
 }
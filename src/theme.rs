@@ -0,0 +1,372 @@
+use bevy::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
+use bevy::ecs::system::SystemParam;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use crate::components::*;
+use crate::card_atlas::{face_index, CardAtlas, BACK_INDEX};
+use crate::feedback::spawn_toast;
+use crate::settings::{save_setting, setting};
+use crate::tween::Animating;
+use tracing::{debug, warn};
+
+const THEMES_FOLDER: &str = "sprites/cards"; // Deck manifests are looked for here and in its subfolders
+const MANIFEST_EXTENSION: &str = "deck";
+const DEFAULT_RANKS: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+
+/// Where one suit's art is: a folder (relative to the manifest) and a filename in which `{rank}`
+/// stands for the rank's name from the manifest's `ranks` and `{value}` for its number, 1 to 13
+#[derive(Clone, Debug)]
+struct SuitArt {
+    folder: String,
+    pattern: String,
+}
+
+/// A set of card art, described by a `.deck` manifest in assets/sprites/cards or one of its
+/// subfolders, so a new deck needs no code. A manifest is `key = value` lines, `#` starting a comment:
+///
+/// ```text
+/// name = Rustaceans
+/// card_size = 1440 2175          # Pixel size of every image
+/// back = CardBack.png
+/// ranks = A 2 3 4 5 6 7 8 9 10 J Q K
+/// hearts.folder = King
+/// hearts.pattern = KingCard{rank}.png
+/// ...                            # And the same for diamonds, clubs and spades
+/// ```
+///
+/// A manifest with `faces = drawn` has no face images: the faces are drawn by the game instead.
+#[derive(Clone, Debug)]
+pub struct DeckTheme {
+    pub name: String,
+    pub card_size: UVec2,
+    folder: String, // Asset path of the manifest's folder, which the image paths are relative to
+    back: String,
+    ranks: Vec<String>,
+    suits: Option<[SuitArt; 4]>, // In CardSuit order. None when the faces are drawn.
+}
+
+impl Default for DeckTheme {
+    /// The bundled Rust mascot deck, for when its manifest can't be read
+    fn default() -> Self {
+        let suit = |folder: &str, pattern: &str| SuitArt { folder: folder.to_string(), pattern: pattern.to_string() };
+        DeckTheme {
+            name: "Rustaceans".to_string(),
+            card_size: UVec2::new(1440, 2175),
+            folder: THEMES_FOLDER.to_string(),
+            back: "CardBack.png".to_string(),
+            ranks: DEFAULT_RANKS.iter().map(|rank| rank.to_string()).collect(),
+            suits: Some([
+                suit("King", "KingCard{rank}.png"),
+                suit("Stabby", "StabbyCard{rank}.png"),
+                suit("EvilFerris", "EvilFerris{rank}.png"),
+                suit("Corro", "CorroCard{rank}.png"),
+            ]),
+        }
+    }
+}
+
+impl DeckTheme {
+    /// Reads a manifest. `folder` is the asset path of the folder it was found in.
+    pub fn parse(manifest: &str, folder: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut card_size = None;
+        let mut back = None;
+        let mut drawn_faces = false;
+        let mut ranks: Vec<String> = DEFAULT_RANKS.iter().map(|rank| rank.to_string()).collect();
+        let mut folders: [Option<String>; 4] = Default::default();
+        let mut patterns: [Option<String>; 4] = Default::default();
+
+        for (number, line) in manifest.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `key = value`", number + 1));
+            };
+            let (key, value) = (key.trim(), value.trim().to_string());
+
+            match key {
+                "name" => name = Some(value),
+                "back" => back = Some(value),
+                "faces" => match value.as_str() {
+                    "drawn" => drawn_faces = true,
+                    "images" => drawn_faces = false,
+                    _ => return Err(format!("line {}: faces should be `images` or `drawn`", number + 1)),
+                },
+                "card_size" => {
+                    let size: Vec<u32> = value.split_whitespace().filter_map(|part| part.parse().ok()).collect();
+                    let [width, height] = size[..] else {
+                        return Err(format!("line {}: card_size should be a width and a height in pixels", number + 1));
+                    };
+                    card_size = Some(UVec2::new(width, height));
+                }
+                "ranks" => {
+                    ranks = value.split_whitespace().map(str::to_string).collect();
+                    if ranks.len() != 13 {
+                        return Err(format!("line {}: ranks should name all 13 ranks, Ace to King", number + 1));
+                    }
+                }
+                _ => {
+                    let suit = key.split_once('.').and_then(|(suit, field)| Some((suit_index(suit)?, field)));
+                    match suit {
+                        Some((suit, "folder")) => folders[suit] = Some(value),
+                        Some((suit, "pattern")) => patterns[suit] = Some(value),
+                        _ => return Err(format!("line {}: unknown key `{}`", number + 1, key)),
+                    }
+                }
+            }
+        }
+
+        let missing = |what: &str| format!("no {} given", what);
+        let suits = if drawn_faces {
+            card_size = card_size.or(Some(CARD_SIZE.as_uvec2())); // No images to size
+            None
+        } else {
+            let mut suits = Vec::new();
+            for (suit, suit_name) in SUIT_NAMES.iter().enumerate() {
+                suits.push(SuitArt {
+                    folder: folders[suit].take().unwrap_or_default(), // A deck may keep its faces next to the manifest
+                    pattern: patterns[suit].take().ok_or_else(|| missing(&format!("{}.pattern", suit_name)))?,
+                });
+            }
+            Some(suits.try_into().map_err(|_| missing("suits"))?)
+        };
+
+        Ok(DeckTheme {
+            name: name.ok_or_else(|| missing("name"))?,
+            card_size: card_size.ok_or_else(|| missing("card_size"))?,
+            folder: folder.to_string(),
+            back: back.ok_or_else(|| missing("back"))?,
+            ranks,
+            suits,
+        })
+    }
+
+    /// Asset path of a card's face, or None when the theme's faces are drawn
+    pub fn front_image(&self, suit: CardSuit, value: u8) -> Option<String> {
+        let art = &self.suits.as_ref()?[suit as usize];
+        let rank = self.ranks.get((value as usize).wrapping_sub(1)).map_or("?", String::as_str);
+        let filename = art.pattern.replace("{rank}", rank).replace("{value}", &value.to_string());
+        Some(join_asset_path(&[&self.folder, &art.folder, &filename]))
+    }
+
+    /// Asset path of the back every card shares
+    pub fn back_image(&self) -> String {
+        join_asset_path(&[&self.folder, &self.back])
+    }
+}
+
+// Manifest names of the suits, in CardSuit order
+const SUIT_NAMES: [&str; 4] = ["hearts", "diamonds", "clubs", "spades"];
+
+fn suit_index(name: &str) -> Option<usize> {
+    SUIT_NAMES.iter().position(|suit| *suit == name)
+}
+
+/// Joins asset path parts with `/`, skipping empty ones and `.`
+fn join_asset_path(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|part| part.trim_matches('/'))
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Every deck theme found at startup and the one in use. --theme picks it by name and T switches to the next.
+#[derive(Resource)]
+pub struct DeckThemes {
+    pub themes: Vec<DeckTheme>,
+    pub current: usize,
+}
+
+impl DeckThemes {
+    /// Reads every manifest in the themes folder and picks `chosen` if it names one of them.
+    /// A broken manifest is skipped with a warning, and with none at all the bundled deck is used.
+    pub fn load(chosen: Option<&str>) -> Self {
+        let assets = FileAssetReader::get_base_path().join("assets");
+        let mut manifests = Vec::new();
+        let mut folders = vec![THEMES_FOLDER.to_string()];
+        if let Ok(entries) = std::fs::read_dir(assets.join(THEMES_FOLDER)) {
+            for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+                folders.push(format!("{}/{}", THEMES_FOLDER, entry.file_name().to_string_lossy()));
+            }
+        }
+        for folder in folders {
+            let Ok(entries) = std::fs::read_dir(assets.join(&folder)) else { continue };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.extension().is_some_and(|extension| extension == MANIFEST_EXTENSION) {
+                    manifests.push((folder.clone(), path));
+                }
+            }
+        }
+        manifests.sort_by(|a, b| a.1.cmp(&b.1)); // Same order on every platform
+
+        let mut themes = Vec::new();
+        for (folder, path) in manifests {
+            let theme = std::fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|manifest| DeckTheme::parse(&manifest, &folder));
+            match theme {
+                Ok(theme) => {
+                    debug!("Deck theme {:?} from {}, {}x{} pixel cards", theme.name, path.display(), theme.card_size.x, theme.card_size.y);
+                    themes.push(theme);
+                }
+                Err(error) => warn!("Skipping deck theme {}: {}", path.display(), error),
+            }
+        }
+        if themes.is_empty() {
+            warn!("No deck themes found in {}, using the bundled deck", assets.join(THEMES_FOLDER).display());
+            themes.push(DeckTheme::default());
+        }
+
+        let current = chosen
+            .and_then(|chosen| themes.iter().position(|theme| theme.name.eq_ignore_ascii_case(chosen)))
+            .unwrap_or(0);
+        DeckThemes { themes, current }
+    }
+
+    pub fn current(&self) -> &DeckTheme {
+        &self.themes[self.current]
+    }
+}
+
+/// Reads the deck manifests at startup, before the first deal, and picks the theme named by --theme
+/// or the one the player chose last time
+pub fn load_deck_themes(mut commands: Commands) {
+    let chosen = setting("--theme", "theme");
+    commands.insert_resource(DeckThemes::load(chosen.as_deref()));
+}
+
+/// What card sprites are drawn with: the card atlas, packed from the deck theme and the card back in use.
+/// Cards are drawn by atlas slot, so the sides of a card are slot numbers.
+#[derive(SystemParam)]
+pub struct CardImages<'w> {
+    pub atlas: Res<'w, CardAtlas>,
+}
+
+impl CardImages<'_> {
+    /// A card's face
+    pub fn front(&self, suit: CardSuit, value: u8) -> usize {
+        face_index(suit, value)
+    }
+
+    /// The chosen card back, or the deck theme's own
+    pub fn back(&self) -> usize {
+        BACK_INDEX
+    }
+
+    /// The side of the card that is showing
+    pub fn showing(&self, card_data: &CardData) -> usize {
+        if card_data.is_face_up {
+            self.front(card_data.suit, card_data.value)
+        } else {
+            self.back()
+        }
+    }
+
+    /// A card sprite showing the given side
+    pub fn sprite(&self, index: usize) -> Sprite {
+        Sprite {
+            custom_size: Some(CARD_SIZE),
+            ..Sprite::from_atlas_image(self.atlas.image.clone(), TextureAtlas { layout: self.atlas.layout.clone(), index })
+        }
+    }
+}
+
+/// T switches to the next deck theme, once no card is moving or turning over, and remembers it for next time.
+/// Its images are loaded and packed into the card atlas, which redraws every card at once.
+pub fn theme_switch_system(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut deck_themes: ResMut<DeckThemes>,
+    animating_query: Query<(), (With<Card>, Animating)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+    if !animating_query.is_empty() {
+        debug!("Not switching deck theme while cards are moving");
+        return;
+    }
+
+    deck_themes.current = (deck_themes.current + 1) % deck_themes.themes.len();
+    let theme = deck_themes.current();
+    debug!("Switching to deck theme {:?}", theme.name);
+    save_setting("theme", &theme.name);
+    spawn_toast(&mut commands, format!("Deck: {}", theme.name));
+    next_state.set(GameState::Loading);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bundled_deck_manifest_matches_the_built_in_deck() {
+        let parsed = DeckTheme::parse(include_str!("../assets/sprites/cards/rustaceans.deck"), THEMES_FOLDER).unwrap();
+        let built_in = DeckTheme::default();
+        assert_eq!(parsed.name, built_in.name);
+        assert_eq!(parsed.card_size, built_in.card_size);
+        assert_eq!(parsed.back_image(), built_in.back_image());
+        assert_eq!(parsed.back_image(), "sprites/cards/CardBack.png");
+        for suit in [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Clubs, CardSuit::Spades] {
+            for value in 1..=13 {
+                assert_eq!(parsed.front_image(suit, value), built_in.front_image(suit, value));
+            }
+        }
+        assert_eq!(parsed.front_image(CardSuit::Clubs, 12).as_deref(), Some("sprites/cards/EvilFerris/EvilFerrisQ.png"));
+    }
+
+    #[test]
+    fn a_drawn_deck_has_no_face_images() {
+        let parsed = DeckTheme::parse(include_str!("../assets/sprites/cards/classic.deck"), THEMES_FOLDER).unwrap();
+        assert_eq!(parsed.name, "Classic");
+        assert_eq!(parsed.card_size, CARD_SIZE.as_uvec2()); // No card_size needed
+        assert_eq!(parsed.front_image(CardSuit::Hearts, 1), None);
+        assert_eq!(parsed.back_image(), "sprites/cards/classic/Back.png");
+    }
+
+    #[test]
+    fn patterns_can_use_rank_names_or_numbers() {
+        let manifest = "
+            name = Numbers
+            card_size = 200 300
+            back = back.png
+            ranks = ace two three four five six seven eight nine ten jack queen king
+            hearts.pattern = h{value}.png
+            diamonds.folder = d/
+            diamonds.pattern = {rank}.png
+            clubs.pattern = c{value}.png
+            spades.pattern = s_{rank}_{value}.png
+        ";
+        let parsed = DeckTheme::parse(manifest, "sprites/cards/numbers").unwrap();
+        assert_eq!(parsed.front_image(CardSuit::Hearts, 13).as_deref(), Some("sprites/cards/numbers/h13.png"));
+        assert_eq!(parsed.front_image(CardSuit::Diamonds, 1).as_deref(), Some("sprites/cards/numbers/d/ace.png"));
+        assert_eq!(parsed.front_image(CardSuit::Spades, 11).as_deref(), Some("sprites/cards/numbers/s_jack_11.png"));
+    }
+
+    #[test]
+    fn broken_manifests_say_which_line_is_wrong() {
+        let error = |manifest: &str| DeckTheme::parse(manifest, THEMES_FOLDER).unwrap_err();
+        assert_eq!(error("name = A\nnot a setting"), "line 2: expected `key = value`");
+        assert_eq!(error("faces = painted"), "line 1: faces should be `images` or `drawn`");
+        assert_eq!(error("card_size = 200"), "line 1: card_size should be a width and a height in pixels");
+        assert_eq!(error("ranks = A 2 3"), "line 1: ranks should name all 13 ranks, Ace to King");
+        assert_eq!(error("# Comment\ncolour = red"), "line 2: unknown key `colour`");
+        assert_eq!(error("hearts.size = 3"), "line 1: unknown key `hearts.size`");
+    }
+
+    #[test]
+    fn missing_settings_are_named() {
+        assert_eq!(DeckTheme::parse("faces = drawn\nback = b.png", THEMES_FOLDER).unwrap_err(), "no name given");
+        assert_eq!(DeckTheme::parse("name = A\nfaces = drawn", THEMES_FOLDER).unwrap_err(), "no back given");
+        let no_spades = "name = A\ncard_size = 2 3\nback = b.png\nhearts.pattern = h.png\ndiamonds.pattern = d.png\nclubs.pattern = c.png";
+        assert_eq!(DeckTheme::parse(no_spades, THEMES_FOLDER).unwrap_err(), "no spades.pattern given");
+        let no_size = "name = A\nback = b.png\nhearts.pattern = h\ndiamonds.pattern = d\nclubs.pattern = c\nspades.pattern = s";
+        assert_eq!(DeckTheme::parse(no_size, THEMES_FOLDER).unwrap_err(), "no card_size given");
+    }
+}
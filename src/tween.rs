@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use crate::actions::GameAction;
+use crate::components::*;
+use crate::sound::{PlaySound, SoundEffect};
+use tracing::debug;
+
+// Animation timing
+const MOVE_SECS: f32 = 0.2;
+const FLIP_SECS: f32 = 0.25; // Both halves of a flip together
+const MIN_TWEEN_DISTANCE: f32 = 1.0; // Smaller moves just snap
+const FLYING_Z: f32 = 50.0; // Moving cards are drawn above the board, below the cursor highlight
+pub const DEAL_INTERVAL: f32 = 0.05; // Between one dealt card leaving the stock and the next
+const SHAKE_SECS: f32 = 0.3;
+const SHAKE_DISTANCE: f32 = 8.0; // Furthest the card swings either side
+const SHAKE_SWINGS: f32 = 3.0; // Back-and-forth swings in one shake
+
+/// A card sliding to where it now is. The card's Transform already holds its place in the game, so
+/// every system that reads positions sees the finished move; only the drawn position (its
+/// GlobalTransform) eases from `from` to there. A dealt card waits at `from` for `delay` seconds first.
+#[derive(Component)]
+pub struct CardTween {
+    from: Vec3,
+    elapsed: f32,
+    delay: f32,
+}
+
+impl CardTween {
+    /// Deals a card from `from` (the stock) once `delay` seconds have passed
+    pub fn dealt_from(from: Vec3, delay: f32) -> Self {
+        CardTween { from, elapsed: 0.0, delay }
+    }
+
+    /// How far through the move the card is, from 0 to 1
+    fn progress(&self) -> f32 {
+        ((self.elapsed - self.delay) / MOVE_SECS).clamp(0.0, 1.0)
+    }
+}
+
+/// A card turning over: it narrows to an edge, swaps to the `side` slot of the card atlas, then widens
+/// again. A dealt card waits `delay` seconds, until it has landed, before turning.
+#[derive(Component)]
+pub struct FlipTween {
+    side: usize,
+    elapsed: f32,
+    delay: f32,
+    swapped: bool,
+}
+
+impl FlipTween {
+    pub fn to(side: usize) -> Self {
+        FlipTween::after(side, 0.0)
+    }
+
+    pub fn after(side: usize, delay: f32) -> Self {
+        FlipTween { side, elapsed: 0.0, delay, swapped: false }
+    }
+
+    /// How far through the flip the card is, from 0 to 1
+    fn progress(&self) -> f32 {
+        ((self.elapsed - self.delay) / FLIP_SECS).clamp(0.0, 1.0)
+    }
+}
+
+/// How long after the deal starts the card dealt `order`th lands
+pub fn deal_landing_delay(order: usize) -> f32 {
+    order as f32 * DEAL_INTERVAL + MOVE_SECS
+}
+
+/// A card shaking its head after a move was turned down. It waits until it has slid back into place.
+#[derive(Component, Default)]
+pub struct Shake {
+    elapsed: f32,
+}
+
+/// Cards being animated, which can't be picked up until they have landed
+pub type Animating = Or<(With<CardTween>, With<FlipTween>, With<Shake>)>;
+
+/// Starts quickly and settles gently into place
+fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// Cards that have been moved this frame, other than by the pointer, and aren't already tweening
+type MovedCards<'w, 's> = Query<'w, 's, (Entity, Ref<'static, Transform>, &'static GlobalTransform), (With<Card>, Changed<Transform>, Without<CurrentlyDragging>, Without<CardTween>)>;
+
+/// Notices cards whose Transform has jumped since they were last drawn and starts a tween from where
+/// they were drawn. Runs before transform propagation, while the GlobalTransform still holds last frame's
+/// drawn position. Cards following the pointer are left alone so dragging stays direct, and a card
+/// already on its way simply heads for its new spot.
+pub fn start_card_tweens_system(
+    mut commands: Commands,
+    moved_cards: MovedCards,
+    selected_card: Res<SelectedCard>,
+) {
+    for (entity, transform, global_transform) in moved_cards.iter() {
+        if transform.is_added() || selected_card.0 == Some(entity) {
+            continue; // Newly spawned, or being dragged
+        }
+
+        let drawn = global_transform.translation();
+        if drawn.truncate().distance(transform.translation.truncate()) < MIN_TWEEN_DISTANCE {
+            continue;
+        }
+
+        debug!("Animating {:?} from {:?} to {:?}", entity, drawn, transform.translation);
+        commands.entity(entity).insert(CardTween { from: drawn, elapsed: 0.0, delay: 0.0 });
+    }
+}
+
+/// A click or tap during the deal skips the rest of it: every card still waiting or flying lands
+/// and turns over straight away
+pub fn skip_deal_system(
+    mut game_actions: MessageReader<GameAction>,
+    mut card_tweens: Query<&mut CardTween>,
+    mut flip_tweens: Query<&mut FlipTween>,
+) {
+    if !game_actions.read().any(|action| action.press_position().is_some()) {
+        return;
+    }
+
+    // Only the deal delays its tweens, so anything with a delay is part of it
+    let mut skipped = 0;
+    for mut tween in card_tweens.iter_mut().filter(|tween| tween.delay > 0.0) {
+        tween.elapsed = tween.delay + MOVE_SECS;
+        skipped += 1;
+    }
+    for mut flip in flip_tweens.iter_mut().filter(|flip| flip.delay > 0.0) {
+        flip.elapsed = flip.delay + FLIP_SECS;
+    }
+    if skipped > 0 {
+        debug!("Deal skipped, {} cards landed at once", skipped);
+    }
+}
+
+/// Cards part-way through a slide, flip or shake, with whatever is drawn on them
+type AnimatedCards<'w, 's> = Query<'w, 's, (Entity, &'static Transform, &'static mut GlobalTransform, &'static mut Sprite, Option<&'static mut CardTween>, Option<&'static mut FlipTween>, Option<&'static mut Shake>, Option<&'static Children>), (With<Card>, Animating)>;
+
+/// Draws moving and flipping cards part-way through their animation. Runs after transform
+/// propagation and overwrites the GlobalTransform, so the Transform keeps the card's real place.
+/// Anything drawn on the card (its children, such as a drawn face) is carried along with it.
+pub fn animate_cards_system(
+    mut commands: Commands,
+    mut sounds: MessageWriter<PlaySound>,
+    time: Res<Time>,
+    mut animated_cards: AnimatedCards,
+    mut card_parts_query: Query<(&Transform, &mut GlobalTransform), Without<Card>>,
+) {
+    let delta = time.delta_secs();
+
+    for (entity, transform, mut global_transform, mut sprite, card_tween, flip_tween, shake, children) in animated_cards.iter_mut() {
+        let mut drawn = *transform;
+        let mut sliding = false;
+
+        if let Some(mut tween) = card_tween {
+            // A dealt card makes its sound as it leaves the stock
+            if tween.delay > 0.0 && tween.elapsed <= tween.delay && tween.elapsed + delta > tween.delay {
+                sounds.write(PlaySound(SoundEffect::Deal));
+            }
+            tween.elapsed += delta;
+            let t = tween.progress();
+            drawn.translation = tween.from.lerp(transform.translation, ease_out_cubic(t));
+            if t < 1.0 {
+                drawn.translation.z = transform.translation.z + FLYING_Z;
+                sliding = true;
+            } else {
+                commands.entity(entity).remove::<CardTween>();
+            }
+        }
+
+        if let Some(mut shake) = shake.filter(|_shake| !sliding) {
+            shake.elapsed += delta;
+            let t = (shake.elapsed / SHAKE_SECS).min(1.0);
+
+            // Swing side to side, dying away
+            drawn.translation.x += (t * SHAKE_SWINGS * std::f32::consts::TAU).sin() * SHAKE_DISTANCE * (1.0 - t);
+            if t >= 1.0 {
+                commands.entity(entity).remove::<Shake>();
+            }
+        }
+
+        if let Some(mut flip) = flip_tween {
+            flip.elapsed += delta;
+            let t = flip.progress();
+
+            // Edge-on halfway through: show the other face from here on
+            if t >= 0.5 && !flip.swapped {
+                if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                    atlas.index = flip.side;
+                }
+                flip.swapped = true;
+                sounds.write(PlaySound(SoundEffect::Flip));
+            }
+            drawn.scale.x *= (1.0 - 2.0 * t).abs();
+            if t >= 1.0 {
+                commands.entity(entity).remove::<FlipTween>();
+            }
+        }
+
+        *global_transform = GlobalTransform::from(drawn);
+        for child in children.into_iter().flatten() {
+            if let Ok((part_transform, mut part_global_transform)) = card_parts_query.get_mut(*child) {
+                *part_global_transform = *global_transform * *part_transform;
+            }
+        }
+    }
+}
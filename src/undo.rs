@@ -1,154 +1,95 @@
 use bevy::prelude::*;
-use bevy::input::ButtonInput;
-use bevy::input::mouse::MouseButton;
-use bevy::input::keyboard::KeyCode;
+use bevy::ecs::system::SystemParam;
+use std::collections::{HashMap, HashSet};
 use crate::components::*;
-use crate::utils::get_card_back_image;
+use crate::actions::GameAction;
+use crate::card_entity::create_card_entity;
+use crate::cursor::PileCursor;
+use crate::golf::{GolfBoard, GolfSlot};
+use crate::pyramid::{PyramidBoard, PyramidDiscard, PyramidSlot};
+use crate::theme::CardImages;
+use crate::tween::FlipTween;
 use tracing::debug;
 
-/// Handles undo button clicks and executes undo actions
-pub fn undo_button_system(
-    mut commands: Commands,
-    mut undo_stack: ResMut<UndoStack>,
-    mut stock_cards: ResMut<StockCards>,
-    asset_server: Res<AssetServer>,
-    mut transform_query: Query<&mut Transform, With<Card>>,
-    mut card_data_query: Query<&mut CardData, With<Card>>,
-    undo_button_query: Query<&Transform, (With<UndoButton>, Without<Card>)>,
-    window_query: Query<&Window>,
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
-) {
-    if mouse_button_input.just_pressed(MouseButton::Left) {
-        // Get cursor position from window
-        let Ok(window) = window_query.single() else { return };
-        let Some(cursor_pos) = window.cursor_position() else { return };
-        
-        // Convert cursor position to world coordinates
-        // The undo button is at (WINDOW_WIDTH/2 - 100, WINDOW_HEIGHT/2 - 50) = (540, 310)
-        // We need to convert screen coordinates to world coordinates
-        let cursor_world_pos = Vec2::new(
-            cursor_pos.x - window.width() / 2.0, // Center at window center
-            window.height() / 2.0 - cursor_pos.y  // Center at window center, flip Y
-        );
-        
-        
-        // Check if undo button was clicked
-        for undo_transform in undo_button_query.iter() {
-            let button_pos = undo_transform.translation;
-            let distance = (cursor_world_pos - button_pos.truncate()).length();
-            if distance < 60.0 { // Within button bounds (increased for better usability)
-                // Proper undo: restore card to previous state
-                if let Some(undo_action) = undo_stack.0.pop() {
-                    debug!("Executing undo action for entity: {:?}", undo_action.card_entity);
-                    
-                    // CRITICAL FIX: Check if the entity still exists before trying to modify it
-                    // This prevents crashes when entities have been despawned and recreated
-                    if !commands.get_entity(undo_action.card_entity).is_ok() {
-                        continue; // Skip this undo action
-                    }
-                    
-                    // First, remove all current components from the card
-                    commands.entity(undo_action.card_entity)
-                        .remove::<TableauPile>()
-                        .remove::<WastePile>()
-                        .remove::<FoundationPile>()
-                        .remove::<StockPile>()
-                        .remove::<Draggable>()
-                        .remove::<CardFront>()
-                        .remove::<CardBack>();
-                    
-                    // Add back the original components
-                    let mut entity_commands = commands.entity(undo_action.card_entity);
-                    for component_type in &undo_action.from_components {
-                        match component_type {
-                            ComponentType::TableauPile => entity_commands.insert(TableauPile),
-                            ComponentType::WastePile => entity_commands.insert(WastePile),
-                            ComponentType::FoundationPile => entity_commands.insert(FoundationPile),
-                            ComponentType::StockPile => entity_commands.insert(StockPile),
-                            ComponentType::Draggable => entity_commands.insert(Draggable),
-                            ComponentType::CardFront => entity_commands.insert(CardFront),
-                            ComponentType::CardBack => entity_commands.insert(CardBack),
-                        };
-                    }
-                    
-                    // Restore the original face up/down state
-                    if let Ok(mut card_data) = card_data_query.get_mut(undo_action.card_entity) {
-                        card_data.is_face_up = undo_action.original_face_up;
-                    }
-                    
-                    // Move the main card back to its original position
-                    if let Ok(mut transform) = transform_query.get_mut(undo_action.card_entity) {
-                        transform.translation = undo_action.from_position;
-                    }
-                    
-                    // CRITICAL FIX: If we're restoring a card to the stock pile, we need to add it back to the stock data
-                    // This prevents the "unusable card" bug where cards appear in stock but aren't actually part of the stock pile
-                    if undo_action.from_components.contains(&ComponentType::StockPile) {
-                        // This card is being restored to the stock pile
-                        // We need to add it back to the stock_cards resource
-                        if let Ok(card_data) = card_data_query.get(undo_action.card_entity) {
-                            // Add the card back to the stock pile data structure
-                            stock_cards.0.push((card_data.suit, card_data.value));
-                            
-                            // CRITICAL FIX: Also restore the card back sprite since stock cards are face down
-                            commands.entity(undo_action.card_entity).insert(Sprite {
-                                image: asset_server.load(get_card_back_image(card_data.suit)),
-                                custom_size: Some(Vec2::new(80.0, 120.0)),
-                                ..default()
-                            });
-                        }
-                    }
-                    
-                    // Move stacked cards back to their original positions
-                    for (card_entity, original_pos) in undo_action.stack_cards {
-                        if let Ok(mut transform) = transform_query.get_mut(card_entity) {
-                            transform.translation = original_pos;
-                        }
-                    }
-                    
-                } else {
-                }
-            }
+/// Everything an UndoAction records: the cards, and the piles the game keeps as data
+#[derive(SystemParam)]
+pub struct UndoableBoard<'w, 's> {
+    cards: Query<'w, 's, EntityRef<'static>, With<Card>>,
+    card_images: CardImages<'w>,
+    stock_cards: ResMut<'w, StockCards>,
+    foundation_piles: ResMut<'w, FoundationPiles>,
+    free_cells: ResMut<'w, FreeCells>,
+    game_score: ResMut<'w, GameScore>,
+    pyramid_board: ResMut<'w, PyramidBoard>,
+    pyramid_discard: ResMut<'w, PyramidDiscard>,
+    golf_board: ResMut<'w, GolfBoard>,
+    win_messages: Query<'w, 's, Entity, With<WinMessage>>,
+}
+
+impl UndoableBoard<'_, '_> {
+    /// The board as it stands
+    pub fn snapshot(&self) -> UndoAction {
+        let cards = self.cards
+            .iter()
+            .filter_map(|card| {
+                Some(CardState {
+                    entity: card.id(),
+                    card_data: card.get::<CardData>()?.clone(),
+                    position: card.get::<Transform>()?.translation,
+                    original_position: card.get::<OriginalPosition>().map(|original| original.0),
+                    components: recorded_components(&card),
+                })
+            })
+            .collect();
+
+        UndoAction {
+            cards,
+            stock_cards: self.stock_cards.0.clone(),
+            foundation_piles: self.foundation_piles.0.clone(),
+            free_cells: self.free_cells.0.clone(),
+            score: self.game_score.0,
+            pyramid_board: self.pyramid_board.0.clone(),
+            pyramid_discard: self.pyramid_discard.0.clone(),
+            golf_cards: self.golf_board.cards.clone(),
+            golf_streak: self.golf_board.streak,
         }
     }
-}
 
-/// Handles keyboard-based undo functionality (Ctrl+Z or Ctrl+U)
-pub fn undo_system(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut undo_stack: ResMut<UndoStack>,
-    mut stock_cards: ResMut<StockCards>,
-    asset_server: Res<AssetServer>,
-    mut transform_query: Query<&mut Transform, With<Card>>,
-    mut card_data_query: Query<&mut CardData, With<Card>>,
-    mut commands: Commands,
-) {
-    // Undo on Ctrl+Z or Ctrl+U
-    if (keyboard_input.pressed(KeyCode::ControlLeft) || keyboard_input.pressed(KeyCode::ControlRight)) 
-        && (keyboard_input.just_pressed(KeyCode::KeyZ) || keyboard_input.just_pressed(KeyCode::KeyU)) {
-        
-        if let Some(undo_action) = undo_stack.0.pop() {
-            
-            // CRITICAL FIX: Check if the entity still exists before trying to modify it
-            // This prevents crashes when entities have been despawned and recreated
-            if !commands.get_entity(undo_action.card_entity).is_ok() {
-                return; // Skip this undo action
+    /// Puts the board back the way `undo_action` recorded it. Cards dealt since are despawned and cards
+    /// recycled since are spawned again, and `undo_action` is updated to point at the new entities.
+    /// Any win message goes too: an undone winning move is no longer a win.
+    pub fn restore(&mut self, commands: &mut Commands, undo_action: &mut UndoAction) {
+        let recorded: HashSet<Entity> = undo_action.cards.iter().map(|card| card.entity).collect();
+        for card in self.cards.iter() {
+            if !recorded.contains(&card.id()) {
+                commands.entity(card.id()).despawn();
             }
-            
-            // First, remove all current components from the card
-            commands.entity(undo_action.card_entity)
-                .remove::<TableauPile>()
-                .remove::<WastePile>()
-                .remove::<FoundationPile>()
-                .remove::<StockPile>()
-                .remove::<Draggable>()
-                .remove::<CardFront>()
-                .remove::<CardBack>();
-            
-            // Add back the original components
-            let mut entity_commands = commands.entity(undo_action.card_entity);
-            for component_type in &undo_action.from_components {
-                match component_type {
+        }
+
+        let mut respawned = HashMap::new();
+        for card in undo_action.cards.iter_mut() {
+            if self.cards.contains(card.entity) {
+                // Clear the card's piles (and any flip in progress, which would turn it to the wrong side)
+                commands.entity(card.entity)
+                    .remove::<(TableauPile, WastePile, FoundationPile, StockPile, Draggable, CardFront, CardBack)>()
+                    .remove::<(FreeCellPile, ReservePile, SkippedWasteCard, PyramidSlot, GolfSlot, OriginalPosition, FlipTween)>()
+                    .insert((
+                        Transform::from_translation(card.position), // Slides back there
+                        card.card_data.clone(),
+                        self.card_images.sprite(self.card_images.showing(&card.card_data)),
+                    ));
+            } else {
+                let entity = create_card_entity(commands, &self.card_images, card.position, card.card_data.id(), card.card_data.is_face_up, ());
+                respawned.insert(card.entity, entity);
+                card.entity = entity;
+            }
+
+            let mut entity_commands = commands.entity(card.entity);
+            if let Some(original_position) = card.original_position {
+                entity_commands.insert(OriginalPosition(original_position));
+            }
+            for component_type in &card.components {
+                match *component_type {
                     ComponentType::TableauPile => entity_commands.insert(TableauPile),
                     ComponentType::WastePile => entity_commands.insert(WastePile),
                     ComponentType::FoundationPile => entity_commands.insert(FoundationPile),
@@ -156,45 +97,301 @@ pub fn undo_system(
                     ComponentType::Draggable => entity_commands.insert(Draggable),
                     ComponentType::CardFront => entity_commands.insert(CardFront),
                     ComponentType::CardBack => entity_commands.insert(CardBack),
+                    ComponentType::FreeCellPile => entity_commands.insert(FreeCellPile),
+                    ComponentType::ReservePile => entity_commands.insert(ReservePile),
+                    ComponentType::SkippedWasteCard => entity_commands.insert(SkippedWasteCard),
+                    ComponentType::PyramidSlot(slot) => entity_commands.insert(PyramidSlot(slot)),
+                    ComponentType::GolfSlot(slot) => entity_commands.insert(GolfSlot(slot)),
                 };
             }
-            
-            // Restore the original face up/down state
-            if let Ok(mut card_data) = card_data_query.get_mut(undo_action.card_entity) {
-                card_data.is_face_up = undo_action.original_face_up;
-            }
-            
-            // Move the main card back to its original position
-            if let Ok(mut transform) = transform_query.get_mut(undo_action.card_entity) {
-                transform.translation = undo_action.from_position;
-            }
-            
-            // CRITICAL FIX: If we're restoring a card to the stock pile, we need to add it back to the stock data
-            // This prevents the "unusable card" bug where cards appear in stock but aren't actually part of the stock pile
-            if undo_action.from_components.contains(&ComponentType::StockPile) {
-                // This card is being restored to the stock pile
-                // We need to add it back to the stock_cards resource
-                if let Ok(card_data) = card_data_query.get(undo_action.card_entity) {
-                    // Add the card back to the stock pile data structure
-                    stock_cards.0.push((card_data.suit, card_data.value));
-                    
-                    // CRITICAL FIX: Also restore the card back sprite since stock cards are face down
-                    commands.entity(undo_action.card_entity).insert(Sprite {
-                        image: asset_server.load(get_card_back_image(card_data.suit)),
-                        custom_size: Some(Vec2::new(80.0, 120.0)),
-                        ..default()
-                    });
-                }
+        }
+
+        // The piles kept as data follow any card that was spawned again
+        let remap = |entity: &mut Option<Entity>| {
+            if let Some(new_entity) = entity.and_then(|old_entity| respawned.get(&old_entity)) {
+                *entity = Some(*new_entity);
             }
-            
-            // Move stacked cards back to their original positions
-            for (card_entity, original_pos) in undo_action.stack_cards {
-                if let Ok(mut transform) = transform_query.get_mut(card_entity) {
-                    transform.translation = original_pos;
-                }
+        };
+        undo_action.free_cells.iter_mut().for_each(remap);
+        undo_action.pyramid_board.iter_mut().for_each(remap);
+        undo_action.golf_cards.iter_mut().for_each(remap);
+
+        self.stock_cards.0 = undo_action.stock_cards.clone();
+        self.foundation_piles.0 = undo_action.foundation_piles.clone();
+        self.free_cells.0 = undo_action.free_cells.clone();
+        self.game_score.0 = undo_action.score;
+        self.pyramid_board.0 = undo_action.pyramid_board.clone();
+        self.pyramid_discard.0 = undo_action.pyramid_discard.clone();
+        self.golf_board.cards = undo_action.golf_cards.clone();
+        self.golf_board.streak = undo_action.golf_streak;
+
+        for entity in self.win_messages.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// The boards played so far, the last being the one in play, and the boards undone since
+#[derive(SystemParam)]
+pub struct UndoHistory<'w> {
+    undo_stack: ResMut<'w, UndoStack>,
+    redo_stack: ResMut<'w, RedoStack>,
+}
+
+/// The cards in the player's hand, which an undo would leave stranded
+#[derive(SystemParam)]
+pub struct CardsInHand<'w, 's> {
+    selected_card: Res<'w, SelectedCard>,
+    dragging_query: Query<'w, 's, (), With<CurrentlyDragging>>,
+}
+
+impl CardsInHand<'_, '_> {
+    /// Whether a card has been picked up or is being dragged
+    fn any(&self) -> bool {
+        self.selected_card.0.is_some() || !self.dragging_query.is_empty()
+    }
+}
+
+/// The undo button, pinned to the top right corner of the window
+type UndoButtonQuery<'w, 's> = Query<'w, 's, (&'static Transform, &'static Sprite), (With<UndoButton>, Without<Card>)>;
+
+/// The pile markers an UndoAction keeps for one card
+fn recorded_components(card: &EntityRef) -> Vec<ComponentType> {
+    let markers = [
+        (card.contains::<TableauPile>(), ComponentType::TableauPile),
+        (card.contains::<WastePile>(), ComponentType::WastePile),
+        (card.contains::<FoundationPile>(), ComponentType::FoundationPile),
+        (card.contains::<StockPile>(), ComponentType::StockPile),
+        (card.contains::<Draggable>(), ComponentType::Draggable),
+        (card.contains::<CardFront>(), ComponentType::CardFront),
+        (card.contains::<CardBack>(), ComponentType::CardBack),
+        (card.contains::<FreeCellPile>(), ComponentType::FreeCellPile),
+        (card.contains::<ReservePile>(), ComponentType::ReservePile),
+        (card.contains::<SkippedWasteCard>(), ComponentType::SkippedWasteCard),
+    ];
+    let slots = [
+        card.get::<PyramidSlot>().map(|slot| ComponentType::PyramidSlot(slot.0)),
+        card.get::<GolfSlot>().map(|slot| ComponentType::GolfSlot(slot.0)),
+    ];
+
+    markers
+        .into_iter()
+        .filter_map(|(present, component_type)| present.then_some(component_type))
+        .chain(slots.into_iter().flatten())
+        .collect()
+}
+
+/// Records the board for undo: once as dealt, then after every move, once the cards it turned over
+/// and the runs it completed have settled. A new move can't be redone past, so it clears the redo stack.
+pub fn record_move_system(
+    mut moves: MessageReader<MoveMade>,
+    board: UndoableBoard,
+    history: UndoHistory,
+) {
+    let UndoHistory { mut undo_stack, mut redo_stack } = history;
+    let moved = moves.read().count() > 0;
+    if !moved && !undo_stack.0.is_empty() {
+        return;
+    }
+
+    undo_stack.0.push(board.snapshot());
+    if moved {
+        redo_stack.0.clear();
+    }
+    debug!("Recorded the board for undo, {} moves back", undo_stack.0.len() - 1);
+}
+
+/// Handles undo and redo from the keyboard (Ctrl+Z or Ctrl+U, Ctrl+Y to redo), the gamepad and the undo button
+pub fn undo_system(
+    mut commands: Commands,
+    mut game_actions: MessageReader<GameAction>,
+    mut board: UndoableBoard,
+    history: UndoHistory,
+    mut cursor: ResMut<PileCursor>,
+    cards_in_hand: CardsInHand,
+    undo_button_query: UndoButtonQuery,
+) {
+    let UndoHistory { mut undo_stack, mut redo_stack } = history;
+    let mut undo = false;
+    let mut redo = false;
+    for action in game_actions.read() {
+        match *action {
+            GameAction::Undo => undo = true,
+            GameAction::Redo => redo = true,
+            // The undo button is pinned to the top right corner of the window
+            GameAction::Press(cursor_world_pos) => {
+                undo |= undo_button_query.iter().any(|(undo_transform, undo_sprite)| {
+                    let button_size = undo_sprite.custom_size.unwrap_or(Vec2::new(100.0, 40.0));
+                    Rect::from_center_size(undo_transform.translation.truncate(), button_size).contains(cursor_world_pos)
+                });
             }
-            
-        } else {
+            _ => {}
         }
     }
+    if !undo && !redo {
+        return;
+    }
+
+    // Cards in the player's hand would be left stranded
+    if cards_in_hand.any() {
+        debug!("Not undoing while cards are being dragged");
+        return;
+    }
+
+    if undo {
+        // The last board is the one in play, so there has to be one before it to go back to
+        if undo_stack.0.len() < 2 {
+            debug!("Nothing to undo");
+            return;
+        }
+        let Some(current) = undo_stack.0.pop() else { return };
+        debug!("Undoing the last move, {} moves back left", undo_stack.0.len() - 1);
+        let Some(previous) = undo_stack.0.last_mut() else { return };
+        board.restore(&mut commands, previous);
+        redo_stack.0.push(current);
+    } else {
+        // A redo is the undone board played back, which can itself be undone again
+        let Some(mut next) = redo_stack.0.pop() else {
+            debug!("Nothing to redo");
+            return;
+        };
+        debug!("Redoing a move, {} more to redo", redo_stack.0.len());
+        board.restore(&mut commands, &mut next);
+        undo_stack.0.push(next);
+    }
+
+    // The held card may have moved
+    cursor.held = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_atlas::CardAtlas;
+
+    fn undo_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(CardAtlas::empty());
+        world.insert_resource(StockCards(Vec::new()));
+        world.insert_resource(FoundationPiles(vec![Vec::new(); 4]));
+        world.insert_resource(FreeCells(Vec::new()));
+        world.insert_resource(GameScore(0));
+        world.insert_resource(PyramidBoard::default());
+        world.insert_resource(PyramidDiscard::default());
+        world.insert_resource(GolfBoard::default());
+        world.insert_resource(UndoStack(Vec::new()));
+        world.insert_resource(RedoStack(Vec::new()));
+        world.insert_resource(PileCursor::default());
+        world.insert_resource(SelectedCard(None));
+        world.init_resource::<Messages<MoveMade>>();
+        world.init_resource::<Messages<GameAction>>();
+        world
+    }
+
+    fn spawn_card(world: &mut World, card: CardId, position: Vec3, pile: impl Bundle) -> Entity {
+        let CardId { deck, suit, value } = card;
+        world.spawn((Card, CardData { suit, value, deck, is_face_up: true }, Transform::from_translation(position), CardFront, Draggable, pile)).id()
+    }
+
+    #[test]
+    fn undo_and_redo_step_a_card_between_the_tableau_and_a_foundation() {
+        let mut world = undo_world();
+        let record = world.register_system(record_move_system);
+        let undo = world.register_system(undo_system);
+        let ace = CardId::new(0, CardSuit::Hearts, 1);
+        let tableau_position = Vec3::new(-300.0, 0.0, 1.0);
+        let foundation_position = Vec3::new(-300.0, 200.0, 1.0);
+        let entity = spawn_card(&mut world, ace, tableau_position, (TableauPile, OriginalPosition(tableau_position)));
+
+        world.run_system(record).unwrap(); // The deal
+        assert_eq!(world.resource::<UndoStack>().0.len(), 1);
+
+        // The ace goes up to a foundation, for 10 points
+        world.entity_mut(entity)
+            .insert((Transform::from_translation(foundation_position), OriginalPosition(foundation_position), FoundationPile))
+            .remove::<(TableauPile, Draggable)>();
+        world.resource_mut::<FoundationPiles>().0[0].push(ace);
+        world.resource_mut::<GameScore>().0 = 10;
+        world.write_message(MoveMade);
+        world.run_system(record).unwrap();
+        assert_eq!(world.resource::<UndoStack>().0.len(), 2);
+
+        world.write_message(GameAction::Undo);
+        world.run_system(undo).unwrap();
+        let card = world.entity(entity);
+        assert_eq!(card.get::<Transform>().unwrap().translation, tableau_position);
+        assert_eq!(card.get::<OriginalPosition>().unwrap().0, tableau_position);
+        assert!(card.contains::<TableauPile>() && card.contains::<Draggable>());
+        assert!(!card.contains::<FoundationPile>());
+        assert!(world.resource::<FoundationPiles>().0[0].is_empty());
+        assert_eq!(world.resource::<GameScore>().0, 0);
+        assert_eq!(world.resource::<UndoStack>().0.len(), 1);
+        assert_eq!(world.resource::<RedoStack>().0.len(), 1);
+
+        // Nothing is left to undo past the deal
+        world.write_message(GameAction::Undo);
+        world.run_system(undo).unwrap();
+        assert_eq!(world.resource::<UndoStack>().0.len(), 1);
+
+        world.write_message(GameAction::Redo);
+        world.run_system(undo).unwrap();
+        let card = world.entity(entity);
+        assert_eq!(card.get::<Transform>().unwrap().translation, foundation_position);
+        assert!(card.contains::<FoundationPile>() && !card.contains::<TableauPile>() && !card.contains::<Draggable>());
+        assert_eq!(world.resource::<FoundationPiles>().0[0], vec![ace]);
+        assert_eq!(world.resource::<GameScore>().0, 10);
+        assert!(world.resource::<RedoStack>().0.is_empty());
+    }
+
+    #[test]
+    fn undoing_a_deal_puts_the_card_back_in_the_stock_and_redoing_it_deals_it_again() {
+        let mut world = undo_world();
+        let record = world.register_system(record_move_system);
+        let undo = world.register_system(undo_system);
+        let king = CardId::new(0, CardSuit::Spades, 13);
+        let waste_position = Vec3::new(200.0, 200.0, 1.0);
+        world.resource_mut::<StockCards>().0.push(king);
+        world.run_system(record).unwrap();
+
+        // Deal the king onto the waste
+        world.resource_mut::<StockCards>().0.pop();
+        let dealt = spawn_card(&mut world, king, waste_position, WastePile);
+        world.write_message(MoveMade);
+        world.run_system(record).unwrap();
+
+        world.write_message(GameAction::Undo);
+        world.run_system(undo).unwrap();
+        assert!(world.get_entity(dealt).is_err());
+        assert_eq!(world.resource::<StockCards>().0, vec![king]);
+
+        world.write_message(GameAction::Redo);
+        world.run_system(undo).unwrap();
+        assert!(world.resource::<StockCards>().0.is_empty());
+        let mut waste = world.query_filtered::<(&CardData, &Transform), With<WastePile>>();
+        let (card_data, transform) = waste.single(&world).unwrap();
+        assert_eq!(card_data.id(), king);
+        assert_eq!(transform.translation, waste_position);
+
+        // The redone board now points at the card that was dealt again
+        let redone = &world.resource::<UndoStack>().0[1];
+        assert!(redone.cards.iter().all(|card| world.get_entity(card.entity).is_ok()));
+    }
+
+    #[test]
+    fn a_new_move_clears_the_moves_that_could_be_redone() {
+        let mut world = undo_world();
+        let record = world.register_system(record_move_system);
+        let undo = world.register_system(undo_system);
+        world.run_system(record).unwrap();
+        world.write_message(MoveMade);
+        world.run_system(record).unwrap();
+        world.write_message(GameAction::Undo);
+        world.run_system(undo).unwrap();
+        assert_eq!(world.resource::<RedoStack>().0.len(), 1);
+
+        world.write_message(MoveMade);
+        world.run_system(record).unwrap();
+        assert!(world.resource::<RedoStack>().0.is_empty());
+        assert_eq!(world.resource::<UndoStack>().0.len(), 2);
+    }
 }
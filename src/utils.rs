@@ -1,156 +1,53 @@
 use bevy::prelude::*;
-use crate::components::CardSuit;
+use crate::components::{CardId, CardSuit, RankRules};
+use crate::card_validation::MoveError;
 
-// Direct mapping from filename to card data - more verbose but completely reliable
-pub fn get_card_data_from_filename(filename: &str) -> Option<(CardSuit, u8)> {
-    match filename {
-        // Hearts (King) - format: KingCard{value}.png
-        "sprites/cards/King/KingCardA.png" => Some((CardSuit::Hearts, 1)),
-        "sprites/cards/King/KingCard2.png" => Some((CardSuit::Hearts, 2)),
-        "sprites/cards/King/KingCard3.png" => Some((CardSuit::Hearts, 3)),
-        "sprites/cards/King/KingCard4.png" => Some((CardSuit::Hearts, 4)),
-        "sprites/cards/King/KingCard5.png" => Some((CardSuit::Hearts, 5)),
-        "sprites/cards/King/KingCard6.png" => Some((CardSuit::Hearts, 6)),
-        "sprites/cards/King/KingCard7.png" => Some((CardSuit::Hearts, 7)),
-        "sprites/cards/King/KingCard8.png" => Some((CardSuit::Hearts, 8)),
-        "sprites/cards/King/KingCard9.png" => Some((CardSuit::Hearts, 9)),
-        "sprites/cards/King/KingCard10.png" => Some((CardSuit::Hearts, 10)),
-        "sprites/cards/King/KingCardJ.png" => Some((CardSuit::Hearts, 11)),
-        "sprites/cards/King/KingCardQ.png" => Some((CardSuit::Hearts, 12)),
-        "sprites/cards/King/KingCardK.png" => Some((CardSuit::Hearts, 13)),
-        
-        // Clubs (EvilFerris) - format: EvilFerris{value}.png
-        "sprites/cards/EvilFerris/EvilFerrisA.png" => Some((CardSuit::Clubs, 1)),
-        "sprites/cards/EvilFerris/EvilFerris2.png" => Some((CardSuit::Clubs, 2)),
-        "sprites/cards/EvilFerris/EvilFerris3.png" => Some((CardSuit::Clubs, 3)),
-        "sprites/cards/EvilFerris/EvilFerris4.png" => Some((CardSuit::Clubs, 4)),
-        "sprites/cards/EvilFerris/EvilFerris5.png" => Some((CardSuit::Clubs, 5)),
-        "sprites/cards/EvilFerris/EvilFerris6.png" => Some((CardSuit::Clubs, 6)),
-        "sprites/cards/EvilFerris/EvilFerris7.png" => Some((CardSuit::Clubs, 7)),
-        "sprites/cards/EvilFerris/EvilFerris8.png" => Some((CardSuit::Clubs, 8)),
-        "sprites/cards/EvilFerris/EvilFerris9.png" => Some((CardSuit::Clubs, 9)),
-        "sprites/cards/EvilFerris/EvilFerris10.png" => Some((CardSuit::Clubs, 10)),
-        "sprites/cards/EvilFerris/EvilFerrisJ.png" => Some((CardSuit::Clubs, 11)),
-        "sprites/cards/EvilFerris/EvilFerrisQ.png" => Some((CardSuit::Clubs, 12)),
-        "sprites/cards/EvilFerris/EvilFerrisK.png" => Some((CardSuit::Clubs, 13)),
-        
-        // Diamonds (Stabby) - format: StabbyCard{value}.png
-        "sprites/cards/Stabby/StabbyCardA.png" => Some((CardSuit::Diamonds, 1)),
-        "sprites/cards/Stabby/StabbyCard2.png" => Some((CardSuit::Diamonds, 2)),
-        "sprites/cards/Stabby/StabbyCard3.png" => Some((CardSuit::Diamonds, 3)),
-        "sprites/cards/Stabby/StabbyCard4.png" => Some((CardSuit::Diamonds, 4)),
-        "sprites/cards/Stabby/StabbyCard5.png" => Some((CardSuit::Diamonds, 5)),
-        "sprites/cards/Stabby/StabbyCard6.png" => Some((CardSuit::Diamonds, 6)),
-        "sprites/cards/Stabby/StabbyCard7.png" => Some((CardSuit::Diamonds, 7)),
-        "sprites/cards/Stabby/StabbyCard8.png" => Some((CardSuit::Diamonds, 8)),
-        "sprites/cards/Stabby/StabbyCard9.png" => Some((CardSuit::Diamonds, 9)),
-        "sprites/cards/Stabby/StabbyCard10.png" => Some((CardSuit::Diamonds, 10)),
-        "sprites/cards/Stabby/StabbyCardJ.png" => Some((CardSuit::Diamonds, 11)),
-        "sprites/cards/Stabby/StabbyCardQ.png" => Some((CardSuit::Diamonds, 12)),
-        "sprites/cards/Stabby/StabbyCardK.png" => Some((CardSuit::Diamonds, 13)),
-        
-        // Spades (Corro) - format: CorroCard{value}.png
-        "sprites/cards/Corro/CorroCardA.png" => Some((CardSuit::Spades, 1)),
-        "sprites/cards/Corro/CorroCard2.png" => Some((CardSuit::Spades, 2)),
-        "sprites/cards/Corro/CorroCard3.png" => Some((CardSuit::Spades, 3)),
-        "sprites/cards/Corro/CorroCard4.png" => Some((CardSuit::Spades, 4)),
-        "sprites/cards/Corro/CorroCard5.png" => Some((CardSuit::Spades, 5)),
-        "sprites/cards/Corro/CorroCard6.png" => Some((CardSuit::Spades, 6)),
-        "sprites/cards/Corro/CorroCard7.png" => Some((CardSuit::Spades, 7)),
-        "sprites/cards/Corro/CorroCard8.png" => Some((CardSuit::Spades, 8)),
-        "sprites/cards/Corro/CorroCard9.png" => Some((CardSuit::Spades, 9)),
-        "sprites/cards/Corro/CorroCard10.png" => Some((CardSuit::Spades, 10)),
-        "sprites/cards/Corro/CorroCardJ.png" => Some((CardSuit::Spades, 11)),
-        "sprites/cards/Corro/CorroCardQ.png" => Some((CardSuit::Spades, 12)),
-        "sprites/cards/Corro/CorroCardK.png" => Some((CardSuit::Spades, 13)),
-        
-        // No match found
-        _ => None
-    }
-}
-
-pub fn get_card_back_image(_suit: CardSuit) -> &'static str {
-    "sprites/cards/CardBack.png"
-}
-
-pub fn get_card_front_image(suit: CardSuit, value: u8) -> String {
-    let suit_name = match suit {
-        CardSuit::Hearts => "King",
-        CardSuit::Diamonds => "Stabby", 
-        CardSuit::Clubs => "EvilFerris",
-        CardSuit::Spades => "Corro",
-    };
-    
-    let value_name = match value {
-        1 => "A",
-        2 => "2",
-        3 => "3",
-        4 => "4",
-        5 => "5",
-        6 => "6",
-        7 => "7",
-        8 => "8",
-        9 => "9",
-        10 => "10",
-        11 => "J", 
-        12 => "Q",
-        13 => "K",
-        _ => return format!("sprites/cards/error/invalid_value_{}.png", value), // Fallback for invalid values
-    };
-    
-    // Different suits use different naming conventions:
-    match suit {
-        CardSuit::Hearts | CardSuit::Spades => {
-            // Hearts (King), Spades (Corro): suitName + "Card" + valueName
-            format!("sprites/cards/{}/{}Card{}.png", suit_name, suit_name, value_name)
-        }
-        CardSuit::Diamonds => {
-            // Diamonds (Stabby): suitName + "Card" + valueName
-            format!("sprites/cards/{}/{}Card{}.png", suit_name, suit_name, value_name)
-        }
-        CardSuit::Clubs => {
-            // Clubs (EvilFerris): suitName + valueName (no "Card" prefix)
-            format!("sprites/cards/{}/{}{}.png", suit_name, suit_name, value_name)
-        }
-    }
-}
-
-pub fn can_place_on_card(card_value: u8, target_card_value: u8) -> bool {
+pub fn can_place_on_card(card_value: u8, target_card_value: u8, rules: &RankRules) -> bool {
     // Cards can only be placed on cards with value +1 (descending order)
-    // For example: Queen (12) on King (13), Jack (11) on Queen (12), etc.
-    card_value == target_card_value - 1
+    // For example: Queen (12) on King (13), Jack (11) on Queen (12), etc. (and King on Ace when ranks wrap)
+    rules.is_one_below(card_value, target_card_value)
 }
 
 pub fn is_red_suit(suit: CardSuit) -> bool {
     matches!(suit, CardSuit::Hearts | CardSuit::Diamonds)
 }
 
-pub fn can_place_on_tableau(card_value: u8, card_suit: CardSuit, target_value: u8, target_suit: CardSuit) -> bool {
+pub fn can_place_on_tableau(card_value: u8, card_suit: CardSuit, target_value: u8, target_suit: CardSuit, rules: &RankRules) -> bool {
     // Tableau placement rules: descending order with alternating colors
-    can_place_on_card(card_value, target_value) && is_red_suit(card_suit) != is_red_suit(target_suit)
+    can_place_on_card(card_value, target_value, rules) && is_red_suit(card_suit) != is_red_suit(target_suit)
 }
 
-pub fn is_valid_stack_sequence(cards: &[(CardSuit, u8)]) -> bool {
-    if cards.len() <= 1 {
+/// How neighbouring cards in a run have to relate to each other
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RunRule {
+    AlternatingColors, // Klondike, FreeCell: red on black, black on red
+    SameSuit,          // Spider: every card the same suit
+    Any,               // Yukon, Russian: any face-up cards can be moved together, in or out of sequence
+}
+
+/// Checks a run given in pile order (bottom card first)
+pub fn is_valid_run(cards: &[CardId], rule: RunRule, rules: &RankRules) -> bool {
+    if cards.len() <= 1 || rule == RunRule::Any {
         return true;
     }
     
-    // Sort by value in descending order (highest to lowest)
-    let mut sorted_cards = cards.to_vec();
-    sorted_cards.sort_by(|a, b| b.1.cmp(&a.1));
-    
-    // Check if the sequence is valid (descending order with alternating colors)
-    for i in 0..sorted_cards.len() - 1 {
-        let current = sorted_cards[i];
-        let next = sorted_cards[i + 1];
+    // Check if the sequence is valid (descending order, suits following the rule)
+    for i in 0..cards.len() - 1 {
+        let current = cards[i];
+        let next = cards[i + 1];
         
         // Check descending order (current value should be exactly one higher than next)
-        if current.1 != next.1 + 1 {
+        if !rules.is_one_below(next.value, current.value) {
             return false;
         }
         
-        // Check alternating colors (red on black, black on red)
-        if is_red_suit(current.0) == is_red_suit(next.0) {
+        let suits_match_rule = match rule {
+            // Check alternating colors (red on black, black on red)
+            RunRule::AlternatingColors => is_red_suit(current.suit) != is_red_suit(next.suit),
+            RunRule::SameSuit => current.suit == next.suit,
+            RunRule::Any => true,
+        };
+        if !suits_match_rule {
             return false;
         }
     }
@@ -158,80 +55,116 @@ pub fn is_valid_stack_sequence(cards: &[(CardSuit, u8)]) -> bool {
     true
 }
 
-pub fn has_complete_stack(cards: &[(CardSuit, u8)]) -> bool {
+pub fn has_complete_stack(cards: &[CardId]) -> bool {
+    has_complete_run(cards, RunRule::AlternatingColors)
+}
+
+pub fn has_complete_run(cards: &[CardId], rule: RunRule) -> bool {
     // A complete stack must start with King (13) and end with Ace (1)
-    // All cards must be in descending order with alternating colors
-    if cards.is_empty() || cards[0].1 != 13 {
+    // All cards must be in descending order with suits following the rule
+    if cards.is_empty() || cards[0].value != 13 {
         return false;
     }
     
     // Must end with Ace (1)
-    if cards.last().map_or(true, |card| card.1 != 1) {
+    if cards.last().is_none_or(|card| card.value != 1) {
         return false;
     }
     
-    // Check if the sequence is valid
-    is_valid_stack_sequence(cards)
+    // Check if the sequence is valid (a complete run never wraps)
+    is_valid_run(cards, rule, &RankRules::default())
 }
 
-pub fn is_in_waste_or_stock_area(position: Vec2) -> bool {
-    // Check if a position is in the waste or stock pile areas
-    // These areas should never accept card drops in solitaire
-    let waste_x = -(6.0 * 100.0) / 2.0 + (5.0 * 100.0); // Above Stack 6 (x = 200)
-    let waste_y = 260.0; // WINDOW_HEIGHT / 2.0 - 100.0
-    let stock_x = -(6.0 * 100.0) / 2.0 + (6.0 * 100.0); // Above Stack 7 (x = 300)
-    let stock_y = waste_y;
-    
-    let waste_distance = (position - Vec2::new(waste_x, waste_y)).length();
-    let stock_distance = (position - Vec2::new(stock_x, stock_y)).length();
-    
-    // Use a generous detection radius to prevent any cards from being placed near these areas
-    waste_distance < 80.0 || stock_distance < 80.0
+/// One deck's 52 cards in suit order, tagged with the given deck index
+pub fn standard_deck(deck: u8) -> Vec<CardId> {
+    let mut cards = Vec::new();
+    for suit in [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Clubs, CardSuit::Spades] {
+        for value in 1..=13 {
+            cards.push(CardId::new(deck, suit, value));
+        }
+    }
+    cards
 }
 
-pub fn can_place_on_foundation(card_data: &crate::components::CardData, foundation_pile: &Vec<(CardSuit, u8)>) -> bool {
-    if foundation_pile.is_empty() {
-        // Only Ace can start a foundation pile
-        return card_data.value == 1;
-    }
+/// Shuffles a deck in place, the same way every time for the same seed
+pub fn shuffle_deck<T>(deck: &mut [T], seed: u64) {
+    // xorshift64* - a zero seed would get stuck, so nudge it
+    let mut state = seed ^ 0x9E37_79B9_7F4A_7C15;
+    let mut next_random = move || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    };
     
+    // Fisher-Yates
+    for i in (1..deck.len()).rev() {
+        let j = (next_random() % (i as u64 + 1)) as usize;
+        deck.swap(i, j);
+    }
+}
+
+pub fn can_place_on_foundation(card_data: &crate::components::CardData, foundation_pile: &[CardId], rules: &RankRules) -> bool {
+    check_foundation(card_data, foundation_pile, rules).is_ok()
+}
+
+/// Checks a card against a foundation pile, saying why it can't go there
+pub fn check_foundation(card_data: &crate::components::CardData, foundation_pile: &[CardId], rules: &RankRules) -> Result<(), MoveError> {
     // Get the top card of the foundation pile
-    if let Some((top_suit, top_value)) = foundation_pile.last() {
-        // Must be same suit and one higher value
-        return card_data.suit == *top_suit && card_data.value == top_value + 1;
+    let Some(top) = foundation_pile.last() else {
+        // Only the base rank (an Ace, unless the game says otherwise) can start a foundation pile
+        if card_data.value != rules.foundation_base {
+            return Err(MoveError::FoundationNeedsBase(rules.foundation_base));
+        }
+        return Ok(());
+    };
+    
+    // Must be same suit and one higher value
+    if card_data.suit != top.suit {
+        return Err(MoveError::WrongSuit);
+    }
+    if !rules.is_one_below(top.value, card_data.value) {
+        return Err(MoveError::FoundationOutOfOrder);
     }
     
-    false
+    Ok(())
 }
 
-pub fn can_place_on_tableau_card(selected_card: &crate::components::CardData, target_card: &crate::components::CardData) -> bool {
+/// Checks a card against a tableau card for alternating-colour building, saying why it can't go there
+pub fn check_tableau_card(selected_card: &crate::components::CardData, target_card: &crate::components::CardData, rules: &RankRules) -> Result<(), MoveError> {
     // Target card must be face up
     if !target_card.is_face_up {
         tracing::debug!("TABLEAU REJECTED: Target card is face down");
-        return false;
+        return Err(MoveError::FaceDown);
     }
     
     // Use the existing validation function from utils
-    let can_place = can_place_on_tableau(selected_card.value, selected_card.suit, target_card.value, target_card.suit);
+    let can_place = can_place_on_tableau(selected_card.value, selected_card.suit, target_card.value, target_card.suit, rules);
     
     if !can_place {
         tracing::debug!("TABLEAU REJECTED: Card {:?} (value: {}, suit: {:?}) cannot be placed on {:?} (value: {}, suit: {:?}) - same color: {}, valid sequence: {}", 
                        selected_card.suit, selected_card.value, selected_card.suit,
                        target_card.suit, target_card.value, target_card.suit,
                        is_red_suit(selected_card.suit) == is_red_suit(target_card.suit),
-                       can_place_on_card(selected_card.value, target_card.value));
+                       can_place_on_card(selected_card.value, target_card.value, rules));
+        if !can_place_on_card(selected_card.value, target_card.value, rules) {
+            return Err(MoveError::WrongRank);
+        }
+        return Err(MoveError::WrongColour);
     }
     
-    can_place
+    Ok(())
 }
 
-/// Finds the best tableau target for a card, reusing logic from waste_click.rs
+/// Finds the best tableau target for a card (nearest valid top card, or an empty column)
 pub fn find_best_tableau_target(
     card_data: &crate::components::CardData,
     card_position: bevy::math::Vec3,
     tableau_cards: &[(bevy::prelude::Entity, bevy::math::Vec3, crate::components::CardData)],
     tableau_positions: &[bevy::math::Vec3],
     exclude_entity: Option<bevy::prelude::Entity>,
+    game_mode: crate::game_mode::GameMode,
+    rules: &RankRules,
 ) -> Option<bevy::math::Vec3> {
     let mut best_target: Option<(bevy::math::Vec3, f32)> = None;
     
@@ -250,7 +183,7 @@ pub fn find_best_tableau_target(
         }
         
         // Check if this is a valid placement
-        if can_place_on_tableau_card(card_data, target_card_data) {
+        if game_mode.can_build_on_tableau(card_data, target_card_data, rules) {
             tracing::debug!("TABLEAU VALID: Card {:?} (value: {}, suit: {:?}) can be placed on {:?} (value: {}, suit: {:?})", 
                            card_data.suit, card_data.value, card_data.suit,
                            target_card_data.suit, target_card_data.value, target_card_data.suit);
@@ -265,12 +198,11 @@ pub fn find_best_tableau_target(
                         }
                     }
                     
-                    // Check if this other card is on top of our target
+                    // Check if this other card is on top of our target (stacked cards share X but not Y)
                     let x_same = (other_transform.x - target_transform.x).abs() < 5.0;
-                    let y_same = (other_transform.y - target_transform.y).abs() < 5.0;
                     let z_higher = other_transform.z > target_transform.z;
                     
-                    if x_same && y_same && z_higher {
+                    if x_same && z_higher {
                         is_top_card = false;
                         break;
                     }
@@ -291,14 +223,13 @@ pub fn find_best_tableau_target(
         }
     }
     
-    // If no valid tableau card found, try empty tableau positions (only for Kings)
-    if best_target.is_none() && card_data.value == 13 {
+    // If no valid tableau card found, try empty tableau positions (only for Kings in Klondike)
+    if best_target.is_none() && game_mode.can_fill_empty_column(card_data) {
         for tableau_pos in tableau_positions {
             // Check if this tableau position is empty
             let mut is_empty = true;
-            for (_other_entity, other_transform, _other_card_data) in tableau_cards.iter() {
-                if (other_transform.x - tableau_pos.x).abs() < 5.0 
-                    && (other_transform.y - tableau_pos.y).abs() < 5.0 {
+            for (other_entity, other_transform, _other_card_data) in tableau_cards.iter() {
+                if Some(*other_entity) != exclude_entity && (other_transform.x - tableau_pos.x).abs() < 5.0 {
                     is_empty = false;
                     break;
                 }
@@ -313,3 +244,69 @@ pub fn find_best_tableau_target(
     
     best_target.map(|(pos, _)| pos)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(cards: &[(CardSuit, u8)]) -> Vec<CardId> {
+        cards.iter().map(|&(suit, value)| CardId::new(0, suit, value)).collect()
+    }
+
+    #[test]
+    fn short_runs_are_always_valid() {
+        let rules = RankRules::default();
+        for rule in [RunRule::AlternatingColors, RunRule::SameSuit, RunRule::Any] {
+            assert!(is_valid_run(&[], rule, &rules));
+            assert!(is_valid_run(&run(&[(CardSuit::Hearts, 7)]), rule, &rules));
+        }
+    }
+
+    #[test]
+    fn alternating_colors_needs_red_on_black() {
+        let rules = RankRules::default();
+        let good = run(&[(CardSuit::Spades, 9), (CardSuit::Hearts, 8), (CardSuit::Clubs, 7), (CardSuit::Diamonds, 6)]);
+        assert!(is_valid_run(&good, RunRule::AlternatingColors, &rules));
+        let same_colour = run(&[(CardSuit::Spades, 9), (CardSuit::Clubs, 8)]);
+        assert!(!is_valid_run(&same_colour, RunRule::AlternatingColors, &rules));
+        let gap = run(&[(CardSuit::Spades, 9), (CardSuit::Hearts, 7)]);
+        assert!(!is_valid_run(&gap, RunRule::AlternatingColors, &rules));
+        let ascending = run(&[(CardSuit::Spades, 8), (CardSuit::Hearts, 9)]);
+        assert!(!is_valid_run(&ascending, RunRule::AlternatingColors, &rules));
+    }
+
+    #[test]
+    fn same_suit_needs_one_suit_throughout() {
+        let rules = RankRules::default();
+        let good = run(&[(CardSuit::Spades, 5), (CardSuit::Spades, 4), (CardSuit::Spades, 3)]);
+        assert!(is_valid_run(&good, RunRule::SameSuit, &rules));
+        let mixed = run(&[(CardSuit::Spades, 5), (CardSuit::Spades, 4), (CardSuit::Clubs, 3)]);
+        assert!(!is_valid_run(&mixed, RunRule::SameSuit, &rules));
+        let alternating = run(&[(CardSuit::Spades, 5), (CardSuit::Hearts, 4)]);
+        assert!(!is_valid_run(&alternating, RunRule::SameSuit, &rules));
+    }
+
+    #[test]
+    fn any_rule_accepts_cards_out_of_sequence() {
+        let rules = RankRules::default();
+        let jumble = run(&[(CardSuit::Spades, 2), (CardSuit::Spades, 10), (CardSuit::Hearts, 4)]);
+        assert!(is_valid_run(&jumble, RunRule::Any, &rules));
+    }
+
+    #[test]
+    fn runs_go_from_ace_to_king_only_when_ranks_wrap() {
+        let wrapping = RankRules { foundation_base: 1, wrap: true };
+        let around_the_corner = run(&[(CardSuit::Hearts, 2), (CardSuit::Spades, 1), (CardSuit::Hearts, 13), (CardSuit::Spades, 12)]);
+        assert!(is_valid_run(&around_the_corner, RunRule::AlternatingColors, &wrapping));
+        assert!(!is_valid_run(&around_the_corner, RunRule::AlternatingColors, &RankRules::default()));
+    }
+
+    #[test]
+    fn a_complete_run_is_king_to_ace() {
+        let spades: Vec<CardId> = (1..=13).rev().map(|value| CardId::new(0, CardSuit::Spades, value)).collect();
+        assert!(has_complete_run(&spades, RunRule::SameSuit));
+        assert!(!has_complete_run(&spades[1..], RunRule::SameSuit)); // No King
+        assert!(!has_complete_run(&spades[..12], RunRule::SameSuit)); // No Ace
+        assert!(!has_complete_run(&spades, RunRule::AlternatingColors));
+    }
+}
@@ -1,11 +1,17 @@
 use bevy::prelude::*;
 use crate::components::*;
-use crate::utils::get_card_back_image;
+use crate::layout::Layout;
 
 
+/// Tableau cards that aren't being dragged, with where they lie and which way up they are
+type StackedCards<'w, 's> = Query<'w, 's, (Entity, &'static mut Transform, &'static CardData), (With<TableauPile>, Without<CurrentlyDragging>)>;
+
+/// Fans out every tableau column from its bottom card, using the layout's spacing: face-down cards
+/// sit closer together than face-up ones, and a column too long for the window is squeezed to fit
 pub fn update_tableau_visual_stacking_system(
-    mut tableau_cards: Query<(Entity, &mut Transform, &CardData), (With<TableauPile>, Or<(With<CardFront>, With<Draggable>)>, Without<CurrentlyDragging>)>,
+    mut tableau_cards: StackedCards,
     selected_card: Res<SelectedCard>,
+    layout: Res<Layout>,
     time: Res<Time>,
     mut last_update: Local<f64>,
 ) {
@@ -16,11 +22,11 @@ pub fn update_tableau_visual_stacking_system(
     }
     *last_update = current_time;
     // Group cards by their X position to identify stacks (only X, not Y)
-    let mut stacks: std::collections::HashMap<i32, Vec<(Entity, f32, f32)>> = std::collections::HashMap::new();
+    let mut stacks: std::collections::HashMap<i32, Vec<(Entity, f32, bool)>> = std::collections::HashMap::new();
     
-    // Collect all tableau cards that are either face-up or draggable
+    // Collect all tableau cards, face-up and face-down
     // Skip cards that are currently being dragged
-    for (entity, transform, _card_data) in tableau_cards.iter() {
+    for (entity, transform, card_data) in tableau_cards.iter() {
         // Skip the currently selected card to avoid interfering with dragging
         if let Some(selected) = selected_card.0 {
             if entity == selected {
@@ -31,13 +37,12 @@ pub fn update_tableau_visual_stacking_system(
         // Round to nearest 5 pixels to group cards that are "at the same X position"
         let x_key = (transform.translation.x / 5.0).round() as i32;
         let z_pos = transform.translation.z;
-        let y_pos = transform.translation.y;
         
-        stacks.entry(x_key).or_insert_with(Vec::new).push((entity, z_pos, y_pos));
+        stacks.entry(x_key).or_default().push((entity, z_pos, !card_data.is_face_up));
     }
     
     // For each stack, sort by Z position and apply visual stacking
-    for (_x_pos, mut cards) in stacks.iter_mut() {
+    for (_x_pos, cards) in stacks.iter_mut() {
         if cards.len() > 1 {
             // Sort by Z position (lowest Z = bottom of stack)
             cards.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
@@ -50,23 +55,14 @@ pub fn update_tableau_visual_stacking_system(
                 continue; // Skip this stack if we can't get the bottom card
             };
             
-            // Apply stacking offsets to all cards in the stack
-            for (stack_index, (entity, _z_pos, _original_y)) in cards.iter().enumerate() {
+            // Work out where every card in the column goes, squeezed to fit above the bottom of the window
+            let face_down: Vec<bool> = cards.iter().map(|(_entity, _z_pos, face_down)| *face_down).collect();
+            let stacked_ys = layout.column_ys(base_y, &face_down);
+            
+            for ((entity, _z_pos, _face_down), stacked_y) in cards.iter().zip(stacked_ys) {
                 if let Ok((_entity_id, mut transform, _card_data)) = tableau_cards.get_mut(*entity) {
-                    // Skip cards that are currently being dragged
-                    if let Some(selected) = selected_card.0 {
-                        if *entity == selected {
-                            continue;
-                        }
-                    }
-                    
-                    // Apply stacking offset: each card above gets a 30-pixel Y offset
-                    // This ensures each card shows enough of itself to remain clickable
-                    let stacked_y = base_y - (stack_index as f32 * 30.0);
-                    
-                    // Only update if the position has changed significantly to avoid unnecessary updates
-                    // Use a larger threshold to prevent cards from jumping around after placement
-                    if (transform.translation.y - stacked_y).abs() > 15.0 {
+                    // Only update if the position has actually changed, so cards aren't marked as moved every tick
+                    if (transform.translation.y - stacked_y).abs() > 0.5 {
                         transform.translation.y = stacked_y;
                     }
                 }
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use crate::components::*;
+use crate::card_entity::create_card_entity;
+use crate::layout::Layout;
+use crate::setup::setup_foundations;
+use crate::utils::{shuffle_deck, standard_deck};
+use crate::theme::CardImages;
+
+// Yukon and Russian Solitaire share a board: 7 columns, no stock, foundations where Klondike's are
+const YUKON_COLUMNS: usize = 7;
+const YUKON_FACE_UP: usize = 5;
+
+/// Spawns the Yukon/Russian board: the first column gets a single card, column `n` gets
+/// `n - 1` face-down cards with 5 face-up cards on top, using all 52 cards
+pub fn setup_yukon_board(commands: &mut Commands, card_images: &CardImages, deal_number: u32) {
+    let mut deck = standard_deck(0);
+    shuffle_deck(&mut deck, deal_number as u64);
+
+    setup_foundations(commands);
+
+    let mut cards = deck.into_iter();
+    for column in 0..YUKON_COLUMNS {
+        let face_down = column;
+        let column_size = if column == 0 { 1 } else { face_down + YUKON_FACE_UP };
+
+        for row in 0..column_size {
+            let Some(card) = cards.next() else { break };
+            let position = Layout::tableau_position(column, YUKON_COLUMNS, row);
+
+            if column == 0 || row >= face_down {
+                create_card_entity(
+                    commands,
+                    card_images,
+                    position,
+                    card,
+                    true,
+                    (
+                        Draggable,
+                        TableauPile,
+                        CardFront,
+                        OriginalPosition(position),
+                    ),
+                );
+            } else {
+                create_card_entity(
+                    commands,
+                    card_images,
+                    position,
+                    card,
+                    false,
+                    (
+                        TableauPile,
+                        OriginalPosition(position),
+                        CardBack,
+                    ),
+                );
+            }
+        }
+    }
+
+    let tableau_positions = (0..YUKON_COLUMNS)
+        .map(|column| Layout::tableau_position(column, YUKON_COLUMNS, 0))
+        .collect();
+    commands.insert_resource(TableauPositions(tableau_positions));
+}